@@ -5,12 +5,499 @@ use core::ptr;
 use core::slice;
 use core::mem;
 
+// core has no transcendental functions for f32 in a no_std build; libm
+// supplies the software implementations the hardware FPU doesn't.
+extern crate libm;
+
 // Audio processing constants
 const MAX_CHANNELS: usize = 8;
 const BUFFER_SIZE: usize = 65536;
 const SAMPLE_RATE_MAX: u32 = 192000;
 const DSP_FILTER_ORDER: usize = 8;
 
+// Resampler constants
+const RESAMPLER_TAPS_PER_PHASE: usize = 16;
+const RESAMPLER_NUM_PHASES: usize = 32;
+const RESAMPLER_HISTORY_LEN: usize = RESAMPLER_TAPS_PER_PHASE;
+const RESAMPLER_KAISER_BETA: f32 = 8.0;
+
+// Partitioned convolution reverb constants.
+const CONV_BLOCK_SIZE: usize = 256;
+const CONV_FFT_SIZE: usize = CONV_BLOCK_SIZE * 2;
+const CONV_MAX_PARTITIONS: usize = 64;
+
+// ISO standard 10-band graphic-EQ center frequencies, and the Q used for
+// each RBJ peaking filter.
+const EQ_BAND_FREQS: [f32; 10] = [31.25, 62.5, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0];
+const EQ_Q: f32 = 1.0;
+
+// RBJ audio-EQ-cookbook peaking filter coefficients, normalized by a0:
+// [b0, b1, b2, a1, a2].
+fn rbj_peaking_coeffs(freq: f32, gain_db: f32, sample_rate: f32, q: f32) -> [f32; 5] {
+    let a = libm::powf(10.0, gain_db / 40.0);
+    let w0 = 2.0 * core::f32::consts::PI * freq / sample_rate;
+    let cos_w0 = libm::cosf(w0);
+    let alpha = libm::sinf(w0) / (2.0 * q);
+
+    let b0 = 1.0 + alpha * a;
+    let b1 = -2.0 * cos_w0;
+    let b2 = 1.0 - alpha * a;
+    let a0 = 1.0 + alpha / a;
+    let a1 = -2.0 * cos_w0;
+    let a2 = 1.0 - alpha / a;
+
+    [b0 / a0, b1 / a0, b2 / a0, a1 / a0, a2 / a0]
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    let (mut a, mut b) = (a, b);
+    while b != 0 {
+        let t = b;
+        b = a % b;
+        a = t;
+    }
+    a
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-6 {
+        1.0
+    } else {
+        libm::sinf(x) / x
+    }
+}
+
+// Modified Bessel function of the first kind, order 0:
+// I0(x) = sum_k ((x^2/4)^k / (k!)^2), iterated until a term drops below 1e-10.
+fn bessel_i0(x: f32) -> f32 {
+    let y = (x * x) / 4.0;
+    let mut sum = 1.0f32;
+    let mut term = 1.0f32;
+    let mut k = 1.0f32;
+    loop {
+        term *= y / (k * k);
+        if term < 1e-10 {
+            break;
+        }
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+fn kaiser_window(n: f32, length: f32, beta: f32) -> f32 {
+    let center = (length - 1.0) / 2.0;
+    let ratio = ((n - center) / center).clamp(-1.0, 1.0);
+    let arg = beta * libm::sqrtf((1.0 - ratio * ratio).max(0.0));
+    bessel_i0(arg) / bessel_i0(beta)
+}
+
+// Rational-ratio polyphase resampler: converts an f32 sample stream from
+// `src_rate` to `dst_rate` using a reduced fraction num/den = dst/gcd,
+// src/gcd so the output/input advance ratio exactly tracks dst/src over
+// time instead of drifting from repeated float rounding.
+pub struct Resampler {
+    src_rate: u32,
+    dst_rate: u32,
+    num: u32,
+    den: u32,
+    phase_acc: u32,
+    taps: [[f32; RESAMPLER_TAPS_PER_PHASE]; RESAMPLER_NUM_PHASES],
+    history: [f32; RESAMPLER_HISTORY_LEN],
+    history_pos: usize,
+}
+
+impl Resampler {
+    pub fn new(src_rate: u32, dst_rate: u32) -> Self {
+        let mut resampler = Self {
+            src_rate,
+            dst_rate,
+            num: 1,
+            den: 1,
+            phase_acc: 0,
+            taps: [[0.0; RESAMPLER_TAPS_PER_PHASE]; RESAMPLER_NUM_PHASES],
+            history: [0.0; RESAMPLER_HISTORY_LEN],
+            history_pos: 0,
+        };
+        resampler.reconfigure(src_rate, dst_rate);
+        resampler
+    }
+
+    pub fn reconfigure(&mut self, src_rate: u32, dst_rate: u32) {
+        let g = gcd(src_rate, dst_rate).max(1);
+        self.src_rate = src_rate;
+        self.dst_rate = dst_rate;
+        self.num = (dst_rate / g).max(1);
+        self.den = (src_rate / g).max(1);
+        self.phase_acc = 0;
+
+        // Cutoff at the narrower of the two rates acts as the anti-alias
+        // filter when downsampling and the interpolation filter when
+        // upsampling; it is 1.0 only when the two rates are equal.
+        let norm = if src_rate < dst_rate {
+            src_rate as f32 / dst_rate as f32
+        } else {
+            dst_rate as f32 / src_rate as f32
+        };
+        let center = (RESAMPLER_TAPS_PER_PHASE as f32) / 2.0;
+
+        for phase in 0..RESAMPLER_NUM_PHASES {
+            let frac = phase as f32 / RESAMPLER_NUM_PHASES as f32;
+            for n in 0..RESAMPLER_TAPS_PER_PHASE {
+                let x = n as f32 - center + frac;
+                let sinc_val = sinc(core::f32::consts::PI * norm * x);
+                let window = kaiser_window(n as f32 + frac, RESAMPLER_TAPS_PER_PHASE as f32, RESAMPLER_KAISER_BETA);
+                self.taps[phase][n] = sinc_val * window * norm;
+            }
+        }
+    }
+
+    fn push_history(&mut self, sample: f32) {
+        self.history[self.history_pos] = sample;
+        self.history_pos = (self.history_pos + 1) % RESAMPLER_HISTORY_LEN;
+    }
+
+    fn convolve(&self, phase: usize) -> f32 {
+        let taps = &self.taps[phase];
+        let mut acc = 0.0f32;
+        for n in 0..RESAMPLER_TAPS_PER_PHASE {
+            let idx = (self.history_pos + RESAMPLER_HISTORY_LEN - 1 - n) % RESAMPLER_HISTORY_LEN;
+            acc += taps[n] * self.history[idx];
+        }
+        acc
+    }
+
+    // Consume `input`, writing resampled output into `output`. Returns the
+    // number of output samples actually produced.
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) -> usize {
+        let mut out_count = 0;
+        for &sample in input {
+            if out_count >= output.len() {
+                break;
+            }
+            self.push_history(sample);
+            self.phase_acc += self.num;
+            while self.phase_acc >= self.den {
+                self.phase_acc -= self.den;
+                if out_count >= output.len() {
+                    break;
+                }
+                let phase = ((self.phase_acc as u64 * RESAMPLER_NUM_PHASES as u64) / self.den as u64) as usize
+                    % RESAMPLER_NUM_PHASES;
+                output[out_count] = self.convolve(phase);
+                out_count += 1;
+            }
+        }
+        out_count
+    }
+}
+
+// Named speaker layouts a channel can be configured to read or write.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChannelLayout {
+    Mono = 0,
+    Stereo = 1,
+    Surround51 = 2,
+    Surround71 = 3,
+}
+
+impl ChannelLayout {
+    fn channel_count(self) -> usize {
+        match self {
+            ChannelLayout::Mono => 1,
+            ChannelLayout::Stereo => 2,
+            ChannelLayout::Surround51 => 6,
+            ChannelLayout::Surround71 => 8,
+        }
+    }
+}
+
+// Governs how a fractional ring-buffer read position is reconstructed
+// into a sample when playback speed or resampling ratio is non-integer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InterpolationMode {
+    Nearest,
+    Linear,
+    Cosine,
+    Cubic,
+    Polyphase,
+}
+
+fn catmull_rom(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * ((2.0 * p1)
+        + (-p0 + p2) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (-p0 + 3.0 * p1 - 3.0 * p2 + p3) * t3)
+}
+
+// Direct windowed-sinc lookup for an arbitrary fractional position,
+// reusing the same sinc/Kaiser window as the `Resampler` FIR bank.
+fn polyphase_sample(ring: &[f32], pos: usize, frac: f32) -> f32 {
+    const RADIUS: i32 = 4;
+    let len = ring.len() as i32;
+    let mut acc = 0.0f32;
+    for k in -RADIUS..=RADIUS {
+        let idx = (pos as i32 + k).rem_euclid(len) as usize;
+        let x = k as f32 - frac;
+        let w = sinc(core::f32::consts::PI * x)
+            * kaiser_window((k + RADIUS) as f32, (2 * RADIUS + 1) as f32, RESAMPLER_KAISER_BETA);
+        acc += ring[idx] * w;
+    }
+    acc
+}
+
+// Channel-conversion stage run between deinterleaving and DSP so a channel
+// can accept N input channels and emit M output channels.
+pub enum ChannelOp {
+    Passthrough,
+    Reorder(Vec<usize>),
+    // dst_channels x src_channels coefficient matrix, row-major by output channel.
+    Remix(Vec<f32>),
+    DupMono,
+}
+
+// Apply `op` to one block of interleaved `input` samples, producing a
+// newly interleaved block at `output_channels`.
+fn apply_channel_op(op: &ChannelOp, input_channels: usize, output_channels: usize, input: &[f32]) -> Vec<f32> {
+    let frames = if input_channels == 0 { 0 } else { input.len() / input_channels };
+    let mut output = Vec::new();
+    output.resize(frames * output_channels, 0.0f32);
+
+    match op {
+        ChannelOp::Passthrough => {
+            let n = core::cmp::min(input.len(), output.len());
+            output[..n].copy_from_slice(&input[..n]);
+        },
+        ChannelOp::Reorder(map) => {
+            for frame in 0..frames {
+                for (out_ch, &src_ch) in map.iter().enumerate() {
+                    if out_ch < output_channels && src_ch < input_channels {
+                        output[frame * output_channels + out_ch] = input[frame * input_channels + src_ch];
+                    }
+                }
+            }
+        },
+        ChannelOp::Remix(matrix) => {
+            for frame in 0..frames {
+                for out_ch in 0..output_channels {
+                    let mut acc = 0.0f32;
+                    for in_ch in 0..input_channels {
+                        acc += matrix[out_ch * input_channels + in_ch] * input[frame * input_channels + in_ch];
+                    }
+                    output[frame * output_channels + out_ch] = acc;
+                }
+            }
+        },
+        ChannelOp::DupMono => {
+            for frame in 0..frames {
+                let sample = input[frame * input_channels];
+                for out_ch in 0..output_channels {
+                    output[frame * output_channels + out_ch] = sample;
+                }
+            }
+        },
+    }
+
+    output
+}
+
+// Standard 5.1 (FL, FR, FC, LFE, RL, RR) down to stereo: fold center and
+// surrounds into L/R at 1/sqrt(2) attenuation, drop the LFE channel.
+fn remix_51_to_stereo() -> Vec<f32> {
+    const COEF: f32 = core::f32::consts::FRAC_1_SQRT_2;
+    let mut matrix = Vec::new();
+    matrix.resize(2 * 6, 0.0f32);
+    // L
+    matrix[0] = 1.0;
+    matrix[2] = COEF;
+    matrix[4] = COEF;
+    // R
+    matrix[1 * 6 + 1] = 1.0;
+    matrix[1 * 6 + 2] = COEF;
+    matrix[1 * 6 + 5] = COEF;
+    matrix
+}
+
+// Frequency-domain DSP core: in-place complex FFT/IFFT plus an MDCT/IMDCT
+// pair built on it. Used by `AudioProcessor`'s partitioned convolution
+// reverb and available for future spectral-EQ/analysis work.
+pub mod dsp {
+    pub mod fft {
+        use alloc::vec::Vec;
+        use core::f32::consts::PI;
+
+        #[derive(Clone, Copy, Debug)]
+        pub struct Complex {
+            pub re: f32,
+            pub im: f32,
+        }
+
+        impl Complex {
+            pub const fn new(re: f32, im: f32) -> Self {
+                Self { re, im }
+            }
+
+            pub const fn zero() -> Self {
+                Self { re: 0.0, im: 0.0 }
+            }
+
+            pub fn add(self, other: Complex) -> Complex {
+                Complex::new(self.re + other.re, self.im + other.im)
+            }
+
+            pub fn sub(self, other: Complex) -> Complex {
+                Complex::new(self.re - other.re, self.im - other.im)
+            }
+
+            pub fn mul(self, other: Complex) -> Complex {
+                Complex::new(
+                    self.re * other.re - self.im * other.im,
+                    self.re * other.im + self.im * other.re,
+                )
+            }
+        }
+
+        fn bit_reverse_permute(data: &mut [Complex]) {
+            let n = data.len();
+            let mut j = 0usize;
+            for i in 1..n {
+                let mut bit = n >> 1;
+                while j & bit != 0 {
+                    j ^= bit;
+                    bit >>= 1;
+                }
+                j |= bit;
+                if i < j {
+                    data.swap(i, j);
+                }
+            }
+        }
+
+        // In-place radix-2 Cooley-Tukey FFT. `data.len()` must be a power of two.
+        pub fn fft(data: &mut [Complex]) {
+            let n = data.len();
+            if n < 2 {
+                return;
+            }
+            bit_reverse_permute(data);
+
+            let mut size = 2;
+            while size <= n {
+                let half = size / 2;
+                let angle_step = -2.0 * PI / size as f32;
+                let mut start = 0;
+                while start < n {
+                    for k in 0..half {
+                        let angle = angle_step * k as f32;
+                        let twiddle = Complex::new(libm::cosf(angle), libm::sinf(angle));
+                        let even = data[start + k];
+                        let odd = data[start + k + half].mul(twiddle);
+                        data[start + k] = even.add(odd);
+                        data[start + k + half] = even.sub(odd);
+                    }
+                    start += size;
+                }
+                size *= 2;
+            }
+        }
+
+        // In-place inverse FFT: conjugate, forward FFT, conjugate and scale by 1/n.
+        pub fn ifft(data: &mut [Complex]) {
+            for c in data.iter_mut() {
+                c.im = -c.im;
+            }
+            fft(data);
+            let n = data.len() as f32;
+            for c in data.iter_mut() {
+                c.re /= n;
+                c.im = -c.im / n;
+            }
+        }
+
+        // Forward MDCT: folds a `2N`-sample real block into `N` frequency
+        // coefficients by pre-twiddling `N/2` complex bins, running the
+        // inverse FFT, and post-twiddling the result.
+        pub fn mdct(input: &[f32], output: &mut [f32]) {
+            let n2 = input.len();
+            let n = n2 / 2;
+            let quarter = n2 / 4;
+            if output.len() != n || quarter == 0 {
+                return;
+            }
+
+            let mut bins: Vec<Complex> = Vec::new();
+            bins.resize(quarter, Complex::zero());
+
+            for k in 0..quarter {
+                let re = input[2 * k] - input[n2 - 1 - 2 * k];
+                let im = input[n + 2 * k] + input[n - 1 - 2 * k];
+                let theta = -2.0 * PI * (k as f32 + 0.125) / quarter as f32;
+                let twiddle = Complex::new(libm::cosf(theta), libm::sinf(theta));
+                bins[k] = Complex::new(re, im).mul(twiddle);
+            }
+
+            ifft(&mut bins);
+
+            for k in 0..quarter {
+                let theta = -2.0 * PI * (k as f32 + 0.125) / quarter as f32;
+                let twiddle = Complex::new(libm::cosf(theta), libm::sinf(theta));
+                let v = bins[k].mul(twiddle);
+                output[2 * k] = v.re;
+                output[n - 1 - 2 * k] = -v.im;
+            }
+        }
+
+        // Inverse MDCT: the algebraic mirror of `mdct`, unfolding `N`
+        // coefficients back into a `2N`-sample block via the forward FFT.
+        // Callers overlap-add the result with the previous block's second
+        // half using the standard windowed overlap pattern.
+        pub fn imdct(input: &[f32], output: &mut [f32]) {
+            let n = input.len();
+            let n2 = output.len();
+            let quarter = n / 2;
+            if n2 != 2 * n || quarter == 0 {
+                return;
+            }
+
+            let mut bins: Vec<Complex> = Vec::new();
+            bins.resize(quarter, Complex::zero());
+
+            for k in 0..quarter {
+                let re = input[2 * k];
+                let im = -input[n - 1 - 2 * k];
+                let theta = -2.0 * PI * (k as f32 + 0.125) / quarter as f32;
+                let twiddle = Complex::new(re, im).mul(Complex::new(
+                    libm::cosf(theta),
+                    libm::sinf(theta),
+                ));
+                bins[k] = twiddle;
+            }
+
+            fft(&mut bins);
+
+            let mut half: Vec<f32> = Vec::new();
+            half.resize(n, 0.0f32);
+            for k in 0..quarter {
+                let theta = -2.0 * PI * (k as f32 + 0.125) / quarter as f32;
+                let twiddle = Complex::new(libm::cosf(theta), libm::sinf(theta));
+                let v = bins[k].mul(twiddle);
+                half[2 * k] = v.re;
+                half[n - 1 - 2 * k] = v.im;
+            }
+
+            // Standard odd-symmetric TDAC extension: the second half of an
+            // IMDCT block is the negated mirror of the first half.
+            for i in 0..n {
+                output[i] = half[i];
+                output[n2 - 1 - i] = -half[i];
+            }
+        }
+    }
+}
+
 // Audio sample formats
 #[repr(u32)]
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -30,14 +517,27 @@ pub struct AudioChannel {
     format: AudioFormat,
     buffer: *mut u8,
     buffer_size: u32,
-    read_pos: u32,
+    read_pos: u32, // integer part of the fractional read position
+    read_frac: f32, // fractional part, advanced each read by `playback_rate`
     write_pos: u32,
     active: bool,
+    playback_rate: f32,
+    interpolation_mode: InterpolationMode,
     volume: f32,
     // DSP processing state
     filter_coeffs: [f32; DSP_FILTER_ORDER],
     filter_state: [f32; DSP_FILTER_ORDER],
-    eq_bands: [f32; 10], // 10-band equalizer
+    eq_bands: [f32; 10], // 10-band equalizer, linear gain (1.0 = unity)
+    eq_coeffs: [[f32; 5]; 10], // RBJ peaking coefficients per band: [b0, b1, b2, a1, a2]
+    eq_state: [[f32; 4]; 10], // Direct Form I state per band: [x1, x2, y1, y2]
+    output_rate: u32,
+    resampler: Resampler,
+    input_channels: u16,
+    output_channels: u16,
+    channel_op: ChannelOp,
+    // false = interleaved frames of `input_channels`/`output_channels`
+    // samples each; true = per-channel contiguous blocks.
+    planar: bool,
 }
 
 // Audio effects processor
@@ -48,6 +548,15 @@ pub struct AudioProcessor {
     reverb_enabled: bool,
     reverb_delay: [f32; 1024],
     reverb_index: usize,
+    // Partitioned overlap-add convolution reverb state; active once an
+    // impulse response has been loaded, replacing the delay-line reverb.
+    ir_loaded: bool,
+    ir_partitions: Vec<Vec<dsp::fft::Complex>>,
+    conv_history: Vec<Vec<dsp::fft::Complex>>,
+    conv_history_pos: usize,
+    conv_in_fifo: Vec<f32>,
+    conv_out_fifo: Vec<f32>,
+    conv_overlap: [f32; CONV_BLOCK_SIZE],
 }
 
 // DSP filter types
@@ -68,12 +577,23 @@ impl AudioChannel {
             buffer: ptr::null_mut(),
             buffer_size: 0,
             read_pos: 0,
+            read_frac: 0.0,
             write_pos: 0,
             active: false,
+            playback_rate: 1.0,
+            interpolation_mode: InterpolationMode::Linear,
             volume: 1.0,
             filter_coeffs: [0.0; DSP_FILTER_ORDER],
             filter_state: [0.0; DSP_FILTER_ORDER],
             eq_bands: [1.0; 10],
+            eq_coeffs: [[1.0, 0.0, 0.0, 0.0, 0.0]; 10],
+            eq_state: [[0.0; 4]; 10],
+            output_rate: 48000,
+            resampler: Resampler::new(48000, 48000),
+            input_channels: 2,
+            output_channels: 2,
+            channel_op: ChannelOp::Passthrough,
+            planar: false,
         }
     }
 
@@ -86,6 +606,13 @@ impl AudioChannel {
         self.channels = channels;
         self.format = format;
         self.active = true;
+        self.output_rate = sample_rate;
+        self.resampler.reconfigure(sample_rate, sample_rate);
+        self.input_channels = channels;
+        self.output_channels = channels;
+        self.channel_op = ChannelOp::Passthrough;
+        self.planar = false;
+        self.recompute_eq_coeffs();
 
         // Initialize low-pass filter coefficients (example)
         self.init_filter(FilterType::LowPass, 20000.0);
@@ -93,6 +620,106 @@ impl AudioChannel {
         Ok(())
     }
 
+    // Recompute all ten RBJ peaking-filter coefficient sets from the
+    // current `eq_bands` gains and `sample_rate`.
+    fn recompute_eq_coeffs(&mut self) {
+        for band in 0..10 {
+            let gain_db = 20.0 * libm::log10f(self.eq_bands[band].max(1e-6));
+            self.eq_coeffs[band] = rbj_peaking_coeffs(EQ_BAND_FREQS[band], gain_db, self.sample_rate as f32, EQ_Q);
+        }
+    }
+
+    // Retarget this channel's output to `output_rate`, enabling the
+    // polyphase resampler in `process_audio` whenever it differs from the
+    // channel's configured `sample_rate`.
+    pub fn set_output_rate(&mut self, output_rate: u32) {
+        self.output_rate = output_rate;
+        self.resampler.reconfigure(self.sample_rate, output_rate);
+    }
+
+    // Select how input/output channel counts and layouts map onto each
+    // other; `process_audio` runs this between deinterleaving and DSP.
+    pub fn set_channel_map(&mut self, input_layout: ChannelLayout, output_layout: ChannelLayout) {
+        let input_channels = input_layout.channel_count();
+        let output_channels = output_layout.channel_count();
+        self.input_channels = input_channels as u16;
+        self.output_channels = output_channels as u16;
+
+        self.channel_op = if input_layout == output_layout {
+            ChannelOp::Passthrough
+        } else if input_layout == ChannelLayout::Mono {
+            ChannelOp::DupMono
+        } else if input_layout == ChannelLayout::Surround51 && output_layout == ChannelLayout::Stereo {
+            ChannelOp::Remix(remix_51_to_stereo())
+        } else {
+            // No dedicated matrix for this pair: carry the first shared
+            // channels straight through and drop or zero-fill the rest.
+            let mut map = Vec::new();
+            for out_ch in 0..output_channels {
+                // Out-of-range sources are skipped by Reorder, leaving zeros.
+                map.push(out_ch);
+            }
+            ChannelOp::Reorder(map)
+        };
+    }
+
+    // Select whether `process_audio` treats its input/output byte buffers as
+    // planar (per-channel contiguous blocks) instead of interleaved frames.
+    pub fn set_planar(&mut self, planar: bool) {
+        self.planar = planar;
+    }
+
+    // Set how fast the fractional read position advances per sample read;
+    // 1.0 is normal speed, 0.5 is half-speed, 2.0 is double-speed.
+    pub fn set_playback_rate(&mut self, rate: f32) {
+        self.playback_rate = rate.max(0.0);
+    }
+
+    pub fn set_interpolation_mode(&mut self, mode: InterpolationMode) {
+        self.interpolation_mode = mode;
+    }
+
+    // Read one sample from `ring` at the channel's current fractional read
+    // position using the selected interpolation mode, then advance that
+    // position by `playback_rate`.
+    pub fn read_interpolated(&mut self, ring: &[f32]) -> f32 {
+        if ring.is_empty() {
+            return 0.0;
+        }
+        let len = ring.len();
+        let i0 = self.read_pos as usize % len;
+
+        let sample = match self.interpolation_mode {
+            InterpolationMode::Nearest => {
+                if self.read_frac < 0.5 { ring[i0] } else { ring[(i0 + 1) % len] }
+            },
+            InterpolationMode::Linear => {
+                let i1 = (i0 + 1) % len;
+                ring[i0] * (1.0 - self.read_frac) + ring[i1] * self.read_frac
+            },
+            InterpolationMode::Cosine => {
+                let i1 = (i0 + 1) % len;
+                let mu = (1.0 - libm::cosf(self.read_frac * core::f32::consts::PI)) / 2.0;
+                ring[i0] * (1.0 - mu) + ring[i1] * mu
+            },
+            InterpolationMode::Cubic => {
+                let im1 = (i0 + len - 1) % len;
+                let i1 = (i0 + 1) % len;
+                let i2 = (i0 + 2) % len;
+                catmull_rom(ring[im1], ring[i0], ring[i1], ring[i2], self.read_frac)
+            },
+            InterpolationMode::Polyphase => polyphase_sample(ring, i0, self.read_frac),
+        };
+
+        self.read_frac += self.playback_rate;
+        while self.read_frac >= 1.0 {
+            self.read_frac -= 1.0;
+            self.read_pos = (self.read_pos + 1) % len as u32;
+        }
+
+        sample
+    }
+
     fn init_filter(&mut self, filter_type: FilterType, cutoff_freq: f32) {
         let nyquist = self.sample_rate as f32 / 2.0;
         let normalized_freq = cutoff_freq / nyquist;
@@ -144,15 +771,23 @@ impl AudioChannel {
     }
 
     fn apply_equalizer(&mut self, samples: &mut [f32]) {
-        // Simple 10-band equalizer (placeholder implementation)
-        let bands_per_sample = samples.len() / 10;
-        
-        for (i, chunk) in samples.chunks_mut(bands_per_sample).enumerate() {
-            if i < 10 {
-                for sample in chunk.iter_mut() {
-                    *sample *= self.eq_bands[i];
-                }
+        // Ten cascaded RBJ peaking biquads, one per ISO band, each run in
+        // Direct Form I over every sample in turn.
+        for band in 0..10 {
+            let [b0, b1, b2, a1, a2] = self.eq_coeffs[band];
+            let [mut x1, mut x2, mut y1, mut y2] = self.eq_state[band];
+
+            for sample in samples.iter_mut() {
+                let x0 = *sample;
+                let y0 = b0 * x0 + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+                x2 = x1;
+                x1 = x0;
+                y2 = y1;
+                y1 = y0;
+                *sample = y0;
             }
+
+            self.eq_state[band] = [x1, x2, y1, y2];
         }
     }
 
@@ -163,6 +798,8 @@ impl AudioChannel {
     pub fn set_eq_band(&mut self, band: usize, gain: f32) {
         if band < 10 {
             self.eq_bands[band] = gain.clamp(0.0, 2.0);
+            let gain_db = 20.0 * libm::log10f(self.eq_bands[band].max(1e-6));
+            self.eq_coeffs[band] = rbj_peaking_coeffs(EQ_BAND_FREQS[band], gain_db, self.sample_rate as f32, EQ_Q);
         }
     }
 }
@@ -176,7 +813,57 @@ impl AudioProcessor {
             reverb_enabled: false,
             reverb_delay: [0.0; 1024],
             reverb_index: 0,
+            ir_loaded: false,
+            ir_partitions: Vec::new(),
+            conv_history: Vec::new(),
+            conv_history_pos: 0,
+            conv_in_fifo: Vec::new(),
+            conv_out_fifo: Vec::new(),
+            conv_overlap: [0.0; CONV_BLOCK_SIZE],
+        }
+    }
+
+    // Load an impulse response, splitting it into CONV_BLOCK_SIZE-sample
+    // partitions and pre-computing each partition's spectrum once. This
+    // replaces the delay-line reverb with partitioned overlap-add
+    // convolution against the loaded IR.
+    pub fn load_impulse_response(&mut self, ir: &[f32], _sample_rate: u32) -> Result<(), i32> {
+        if ir.is_empty() {
+            return Err(-1);
+        }
+
+        let partition_count = core::cmp::min(
+            (ir.len() + CONV_BLOCK_SIZE - 1) / CONV_BLOCK_SIZE,
+            CONV_MAX_PARTITIONS,
+        );
+
+        let mut partitions = Vec::new();
+        for p in 0..partition_count {
+            let mut block: Vec<dsp::fft::Complex> = Vec::new();
+            block.resize(CONV_FFT_SIZE, dsp::fft::Complex::zero());
+            let start = p * CONV_BLOCK_SIZE;
+            let end = core::cmp::min(start + CONV_BLOCK_SIZE, ir.len());
+            for i in start..end {
+                block[i - start] = dsp::fft::Complex::new(ir[i], 0.0);
+            }
+            dsp::fft::fft(&mut block);
+            partitions.push(block);
+        }
+
+        self.conv_history = Vec::new();
+        for _ in 0..partitions.len() {
+            let mut zeroed = Vec::new();
+            zeroed.resize(CONV_FFT_SIZE, dsp::fft::Complex::zero());
+            self.conv_history.push(zeroed);
         }
+        self.conv_history_pos = 0;
+        self.conv_in_fifo = Vec::new();
+        self.conv_out_fifo = Vec::new();
+        self.conv_overlap = [0.0; CONV_BLOCK_SIZE];
+        self.ir_partitions = partitions;
+        self.ir_loaded = true;
+
+        Ok(())
     }
 
     pub fn process_audio(&mut self, channel_id: usize, input: &[u8], output: &mut [u8]) -> Result<usize, i32> {
@@ -190,11 +877,37 @@ impl AudioProcessor {
         }
 
         // Convert input to f32 samples based on format
-        let mut samples = self.convert_to_f32(input, channel.format)?;
-        
+        let samples = self.convert_to_f32(input, channel.format)?;
+        let samples = if channel.planar {
+            Self::planar_to_interleaved(&samples, channel.input_channels as usize)
+        } else {
+            samples
+        };
+
+        // Remap/remix channels (e.g. 5.1 -> stereo) before DSP runs.
+        let mut samples = apply_channel_op(
+            &channel.channel_op,
+            channel.input_channels as usize,
+            channel.output_channels as usize,
+            &samples,
+        );
+
         // Apply DSP processing
         channel.apply_dsp(&mut samples);
-        
+
+        // Convert sample rate if the channel's output rate diverges from
+        // its configured input rate.
+        let mut samples = if channel.output_rate != channel.sample_rate {
+            let estimate = (samples.len() as u64 * channel.output_rate as u64) / channel.sample_rate.max(1) as u64;
+            let mut resampled = Vec::new();
+            resampled.resize(estimate as usize + RESAMPLER_TAPS_PER_PHASE, 0.0f32);
+            let produced = channel.resampler.process(&samples, &mut resampled);
+            resampled.truncate(produced);
+            resampled
+        } else {
+            samples
+        };
+
         // Apply master volume
         for sample in samples.iter_mut() {
             *sample *= self.master_volume;
@@ -206,15 +919,73 @@ impl AudioProcessor {
         }
         
         // Convert back to output format
+        let samples = if channel.planar {
+            Self::interleaved_to_planar(&samples, channel.output_channels as usize)
+        } else {
+            samples
+        };
         let bytes_written = self.convert_from_f32(&samples, output, channel.format)?;
-        
+
         Ok(bytes_written)
     }
 
+    // Rearrange a planar (per-channel contiguous) block into interleaved
+    // frames so the rest of `process_audio` can keep working on interleaved
+    // data regardless of the channel's buffer layout.
+    fn planar_to_interleaved(samples: &[f32], channels: usize) -> Vec<f32> {
+        if channels == 0 {
+            return samples.to_vec();
+        }
+        let frames = samples.len() / channels;
+        let mut output = Vec::new();
+        output.resize(frames * channels, 0.0f32);
+        for ch in 0..channels {
+            for frame in 0..frames {
+                output[frame * channels + ch] = samples[ch * frames + frame];
+            }
+        }
+        output
+    }
+
+    // Inverse of `planar_to_interleaved`.
+    fn interleaved_to_planar(samples: &[f32], channels: usize) -> Vec<f32> {
+        if channels == 0 {
+            return samples.to_vec();
+        }
+        let frames = samples.len() / channels;
+        let mut output = Vec::new();
+        output.resize(frames * channels, 0.0f32);
+        for frame in 0..frames {
+            for ch in 0..channels {
+                output[ch * frames + frame] = samples[frame * channels + ch];
+            }
+        }
+        output
+    }
+
     fn convert_to_f32(&self, input: &[u8], format: AudioFormat) -> Result<Vec<f32>, i32> {
         let mut samples = Vec::new();
-        
+
         match format {
+            AudioFormat::Pcm8 => {
+                for &byte in input.iter() {
+                    let sample = (byte as f32 - 128.0) / 128.0;
+                    samples.push(sample);
+                }
+            },
+            AudioFormat::Pcm24 => {
+                if input.len() % 3 != 0 {
+                    return Err(-1);
+                }
+
+                for chunk in input.chunks_exact(3) {
+                    let mut raw = (chunk[0] as i32) | ((chunk[1] as i32) << 8) | ((chunk[2] as i32) << 16);
+                    if raw & 0x0080_0000 != 0 {
+                        raw -= 0x0100_0000;
+                    }
+                    samples.push(raw as f32 / 8_388_608.0);
+                }
+            },
             AudioFormat::Pcm16 => {
                 if input.len() % 2 != 0 {
                     return Err(-1);
@@ -253,8 +1024,31 @@ impl AudioProcessor {
 
     fn convert_from_f32(&self, samples: &[f32], output: &mut [u8], format: AudioFormat) -> Result<usize, i32> {
         let mut bytes_written = 0;
-        
+
         match format {
+            AudioFormat::Pcm8 => {
+                for &sample in samples.iter() {
+                    if bytes_written + 1 > output.len() {
+                        break;
+                    }
+
+                    let sample_u8 = (sample.clamp(-1.0, 1.0) * 127.0 + 128.0) as u8;
+                    output[bytes_written] = sample_u8;
+                    bytes_written += 1;
+                }
+            },
+            AudioFormat::Pcm24 => {
+                for &sample in samples.iter() {
+                    if bytes_written + 3 > output.len() {
+                        break;
+                    }
+
+                    let sample_i32 = (sample.clamp(-1.0, 1.0) * 8_388_607.0) as i32;
+                    let bytes = sample_i32.to_le_bytes();
+                    output[bytes_written..bytes_written + 3].copy_from_slice(&bytes[..3]);
+                    bytes_written += 3;
+                }
+            },
             AudioFormat::Pcm16 => {
                 for (i, &sample) in samples.iter().enumerate() {
                     if bytes_written + 2 > output.len() {
@@ -298,7 +1092,13 @@ impl AudioProcessor {
     }
 
     fn apply_effects(&mut self, samples: &mut [f32]) {
-        if self.reverb_enabled {
+        if !self.reverb_enabled {
+            return;
+        }
+
+        if self.ir_loaded {
+            self.apply_convolution_reverb(samples);
+        } else {
             self.apply_reverb(samples);
         }
     }
@@ -309,11 +1109,63 @@ impl AudioProcessor {
             let delayed = self.reverb_delay[self.reverb_index];
             self.reverb_delay[self.reverb_index] = *sample + delayed * 0.3;
             *sample += delayed * 0.2;
-            
+
             self.reverb_index = (self.reverb_index + 1) % self.reverb_delay.len();
         }
     }
 
+    // Partitioned overlap-add convolution against the loaded impulse
+    // response: each CONV_BLOCK_SIZE-sample block is FFT'd once, multiplied
+    // against every partition's pre-computed spectrum, summed, inverse
+    // FFT'd, and overlap-added with the previous block's tail.
+    fn apply_convolution_reverb(&mut self, samples: &mut [f32]) {
+        self.conv_in_fifo.extend_from_slice(samples);
+
+        while self.conv_in_fifo.len() >= CONV_BLOCK_SIZE {
+            let block: Vec<f32> = self.conv_in_fifo.drain(0..CONV_BLOCK_SIZE).collect();
+
+            let mut spectrum: Vec<dsp::fft::Complex> = Vec::new();
+            spectrum.resize(CONV_FFT_SIZE, dsp::fft::Complex::zero());
+            for (i, &sample) in block.iter().enumerate() {
+                spectrum[i] = dsp::fft::Complex::new(sample, 0.0);
+            }
+            dsp::fft::fft(&mut spectrum);
+
+            self.conv_history[self.conv_history_pos] = spectrum;
+
+            let num_partitions = self.ir_partitions.len();
+            let mut acc: Vec<dsp::fft::Complex> = Vec::new();
+            acc.resize(CONV_FFT_SIZE, dsp::fft::Complex::zero());
+            for p in 0..num_partitions {
+                let hist_idx = (self.conv_history_pos + num_partitions - p) % num_partitions;
+                let hist_spectrum = &self.conv_history[hist_idx];
+                let ir_spectrum = &self.ir_partitions[p];
+                for bin in 0..CONV_FFT_SIZE {
+                    acc[bin] = acc[bin].add(hist_spectrum[bin].mul(ir_spectrum[bin]));
+                }
+            }
+            self.conv_history_pos = (self.conv_history_pos + 1) % core::cmp::max(num_partitions, 1);
+
+            dsp::fft::ifft(&mut acc);
+
+            for i in 0..CONV_BLOCK_SIZE {
+                self.conv_out_fifo.push(acc[i].re + self.conv_overlap[i]);
+            }
+            for i in 0..CONV_BLOCK_SIZE {
+                self.conv_overlap[i] = acc[CONV_BLOCK_SIZE + i].re;
+            }
+        }
+
+        let ready = core::cmp::min(samples.len(), self.conv_out_fifo.len());
+        samples[..ready].copy_from_slice(&self.conv_out_fifo[..ready]);
+        if samples.len() > ready {
+            // Not enough convolved output accumulated yet (startup latency);
+            // leave the remainder silent rather than passing through dry.
+            samples[ready..].fill(0.0);
+        }
+        self.conv_out_fifo.drain(0..ready);
+    }
+
     pub fn set_master_volume(&mut self, volume: f32) {
         self.master_volume = volume.clamp(0.0, 2.0);
     }
@@ -327,6 +1179,9 @@ impl AudioProcessor {
         if !enabled {
             self.reverb_delay.fill(0.0);
             self.reverb_index = 0;
+            self.conv_in_fifo.clear();
+            self.conv_out_fifo.clear();
+            self.conv_overlap = [0.0; CONV_BLOCK_SIZE];
         }
     }
 
@@ -435,6 +1290,134 @@ pub extern "C" fn audio_rust_set_volume(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn audio_rust_set_output_rate(
+    processor: *mut AudioProcessor,
+    channel_id: usize,
+    output_rate: u32,
+) -> i32 {
+    if processor.is_null() {
+        return -1;
+    }
+
+    unsafe {
+        let processor_ref = &mut *processor;
+        if let Some(channel) = processor_ref.get_channel_mut(channel_id) {
+            channel.set_output_rate(output_rate);
+            0
+        } else {
+            -1
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn audio_rust_set_channel_map(
+    processor: *mut AudioProcessor,
+    channel_id: usize,
+    input_layout: u32,
+    output_layout: u32,
+) -> i32 {
+    if processor.is_null() {
+        return -1;
+    }
+
+    let parse_layout = |layout: u32| match layout {
+        0 => Some(ChannelLayout::Mono),
+        1 => Some(ChannelLayout::Stereo),
+        2 => Some(ChannelLayout::Surround51),
+        3 => Some(ChannelLayout::Surround71),
+        _ => None,
+    };
+
+    let (input_layout, output_layout) = match (parse_layout(input_layout), parse_layout(output_layout)) {
+        (Some(i), Some(o)) => (i, o),
+        _ => return -1,
+    };
+
+    unsafe {
+        let processor_ref = &mut *processor;
+        if let Some(channel) = processor_ref.get_channel_mut(channel_id) {
+            channel.set_channel_map(input_layout, output_layout);
+            0
+        } else {
+            -1
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn audio_rust_set_playback_rate(
+    processor: *mut AudioProcessor,
+    channel_id: usize,
+    rate: f32,
+) -> i32 {
+    if processor.is_null() {
+        return -1;
+    }
+
+    unsafe {
+        let processor_ref = &mut *processor;
+        if let Some(channel) = processor_ref.get_channel_mut(channel_id) {
+            channel.set_playback_rate(rate);
+            0
+        } else {
+            -1
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn audio_rust_set_interpolation_mode(
+    processor: *mut AudioProcessor,
+    channel_id: usize,
+    mode: u32,
+) -> i32 {
+    if processor.is_null() {
+        return -1;
+    }
+
+    let interpolation_mode = match mode {
+        0 => InterpolationMode::Nearest,
+        1 => InterpolationMode::Linear,
+        2 => InterpolationMode::Cosine,
+        3 => InterpolationMode::Cubic,
+        4 => InterpolationMode::Polyphase,
+        _ => return -1,
+    };
+
+    unsafe {
+        let processor_ref = &mut *processor;
+        if let Some(channel) = processor_ref.get_channel_mut(channel_id) {
+            channel.set_interpolation_mode(interpolation_mode);
+            0
+        } else {
+            -1
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn audio_rust_set_planar(
+    processor: *mut AudioProcessor,
+    channel_id: usize,
+    planar: bool,
+) -> i32 {
+    if processor.is_null() {
+        return -1;
+    }
+
+    unsafe {
+        let processor_ref = &mut *processor;
+        if let Some(channel) = processor_ref.get_channel_mut(channel_id) {
+            channel.set_planar(planar);
+            0
+        } else {
+            -1
+        }
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn audio_rust_set_master_volume(processor: *mut AudioProcessor, volume: f32) -> i32 {
     if processor.is_null() {
@@ -460,3 +1443,24 @@ pub extern "C" fn audio_rust_enable_effects(processor: *mut AudioProcessor, enab
         0
     }
 }
+
+#[no_mangle]
+pub extern "C" fn audio_rust_load_impulse_response(
+    processor: *mut AudioProcessor,
+    ir: *const f32,
+    ir_len: usize,
+    sample_rate: u32,
+) -> i32 {
+    if processor.is_null() || ir.is_null() {
+        return -1;
+    }
+
+    unsafe {
+        let processor_ref = &mut *processor;
+        let ir_slice = slice::from_raw_parts(ir, ir_len);
+        match processor_ref.load_impulse_response(ir_slice, sample_rate) {
+            Ok(()) => 0,
+            Err(e) => e,
+        }
+    }
+}
@@ -12,6 +12,7 @@ const DRM_MAX_CRTCS: usize = 8;
 const DRM_MAX_PLANES: usize = 16;
 const DRM_MAX_PROPERTIES: usize = 256;
 const DRM_MAX_MODES: usize = 64;
+const DRM_PLANE_MAX_MODIFIERS: usize = 8;
 
 // DRM Object Types
 #[repr(C)]
@@ -97,6 +98,9 @@ pub struct Plane {
     pub crtc_w: u32,
     pub crtc_h: u32,
     pub fb_id: u32,
+    pub crtc_id: u32, // CRTC this plane is currently assigned to, 0 if none
+    pub modifiers: [u64; DRM_PLANE_MAX_MODIFIERS],
+    pub modifier_count: u32,
 }
 
 // CRTC Structure
@@ -112,6 +116,8 @@ pub struct Crtc {
     pub fb_id: u32,
     pub gamma_size: u32,
     pub enabled: bool,
+    pub cursor_max_w: u32,
+    pub cursor_max_h: u32,
 }
 
 // Connector Structure
@@ -178,6 +184,8 @@ static mut DRM_STATE: AtomicState = AtomicState {
         fb_id: 0,
         gamma_size: 256,
         enabled: false,
+        cursor_max_w: 64,
+        cursor_max_h: 64,
     }; DRM_MAX_CRTCS],
     planes: [Plane {
         id: 0,
@@ -194,6 +202,9 @@ static mut DRM_STATE: AtomicState = AtomicState {
         crtc_w: 0,
         crtc_h: 0,
         fb_id: 0,
+        crtc_id: 0,
+        modifiers: [0; DRM_PLANE_MAX_MODIFIERS],
+        modifier_count: 0,
     }; DRM_MAX_PLANES],
     connectors: [Connector {
         id: 0,
@@ -240,6 +251,778 @@ extern "C" {
     fn aur_debug_print(fmt: *const u8, ...);
 }
 
+// DRM Property Types (mirrors the upstream DRM_MODE_PROP_* kinds)
+const DRM_PROP_TYPE_ENUM: u32 = 1;
+const DRM_PROP_TYPE_RANGE: u32 = 2;
+const DRM_PROP_TYPE_SIGNED_RANGE: u32 = 3;
+const DRM_PROP_TYPE_BLOB: u32 = 4;
+const DRM_PROP_TYPE_BITMASK: u32 = 5;
+
+const DRM_PROP_MAX_ENUM_ENTRIES: usize = 8;
+const DRM_MAX_PROPERTY_VALUES: usize = 512;
+
+// Standard property IDs seeded by `drm_seed_standard_properties`.
+const DRM_PROP_ID_DPMS: u32 = 1;
+const DRM_PROP_ID_PLANE_TYPE: u32 = 2;
+const DRM_PROP_ID_SCALING_MODE: u32 = 3;
+const DRM_PROP_ID_ROTATION: u32 = 4;
+const DRM_PROP_ID_CONTENT_TYPE: u32 = 5;
+
+// Rotation bitmask bits (mirrors DRM_MODE_ROTATE_*/DRM_MODE_REFLECT_*).
+const DRM_ROTATE_0: u64 = 1 << 0;
+const DRM_ROTATE_90: u64 = 1 << 1;
+const DRM_ROTATE_180: u64 = 1 << 2;
+const DRM_ROTATE_270: u64 = 1 << 3;
+const DRM_REFLECT_X: u64 = 1 << 4;
+const DRM_REFLECT_Y: u64 = 1 << 5;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct DrmPropertyEnumEntry {
+    pub value: u64,
+    pub name: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct DrmProperty {
+    pub id: u32,
+    pub name: [u8; 32],
+    pub prop_type: u32,
+    pub flags: u32,
+    pub range_min: u64,
+    pub range_max: u64,
+    pub enum_entries: [DrmPropertyEnumEntry; DRM_PROP_MAX_ENUM_ENTRIES],
+    pub enum_count: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct DrmPropertyValue {
+    object_type: u32,
+    object_id: u32,
+    property_id: u32,
+    value: u64,
+    in_use: bool,
+}
+
+struct DrmPropertyManager {
+    properties: [Option<DrmProperty>; DRM_MAX_PROPERTIES],
+    property_count: u32,
+    values: [DrmPropertyValue; DRM_MAX_PROPERTY_VALUES],
+}
+
+static mut DRM_PROPS: DrmPropertyManager = DrmPropertyManager {
+    properties: [None; DRM_MAX_PROPERTIES],
+    property_count: 0,
+    values: [DrmPropertyValue {
+        object_type: 0,
+        object_id: 0,
+        property_id: 0,
+        value: 0,
+        in_use: false,
+    }; DRM_MAX_PROPERTY_VALUES],
+};
+
+fn drm_name(s: &str) -> [u8; 32] {
+    let mut name = [0u8; 32];
+    let bytes = s.as_bytes();
+    let copy_len = core::cmp::min(bytes.len(), 31);
+    name[..copy_len].copy_from_slice(&bytes[..copy_len]);
+    name
+}
+
+fn drm_enum_entry(value: u64, name: &str) -> DrmPropertyEnumEntry {
+    DrmPropertyEnumEntry { value, name: drm_name(name) }
+}
+
+fn drm_enum_property(id: u32, name: &str, entries: &[(u64, &str)]) -> DrmProperty {
+    let mut enum_entries = [DrmPropertyEnumEntry { value: 0, name: [0; 32] }; DRM_PROP_MAX_ENUM_ENTRIES];
+    let count = core::cmp::min(entries.len(), DRM_PROP_MAX_ENUM_ENTRIES);
+    for i in 0..count {
+        enum_entries[i] = drm_enum_entry(entries[i].0, entries[i].1);
+    }
+
+    DrmProperty {
+        id,
+        name: drm_name(name),
+        prop_type: DRM_PROP_TYPE_ENUM,
+        flags: 0,
+        range_min: 0,
+        range_max: 0,
+        enum_entries,
+        enum_count: count as u32,
+    }
+}
+
+fn drm_bitmask_property(id: u32, name: &str, bits: &[(u64, &str)]) -> DrmProperty {
+    let mut prop = drm_enum_property(id, name, bits);
+    prop.prop_type = DRM_PROP_TYPE_BITMASK;
+    prop
+}
+
+// Registers a property definition; silently drops it if the table is full
+// (mirrors the rest of this driver's fixed-capacity "best effort" style).
+fn drm_register_property(props: &mut DrmPropertyManager, prop: DrmProperty) {
+    let idx = prop.id as usize;
+    if idx == 0 || idx > DRM_MAX_PROPERTIES {
+        return;
+    }
+    props.properties[idx - 1] = Some(prop);
+    if idx as u32 > props.property_count {
+        props.property_count = idx as u32;
+    }
+}
+
+// Seeds the standard KMS properties this driver understands. Called once
+// from `aurora_drm_init`.
+fn drm_seed_standard_properties(props: &mut DrmPropertyManager) {
+    drm_register_property(props, drm_enum_property(DRM_PROP_ID_DPMS, "DPMS", &[
+        (0, "On"),
+        (1, "Standby"),
+        (2, "Suspend"),
+        (3, "Off"),
+    ]));
+    drm_register_property(props, drm_enum_property(DRM_PROP_ID_PLANE_TYPE, "type", &[
+        (1, "Primary"),
+        (2, "Overlay"),
+        (3, "Cursor"),
+    ]));
+    drm_register_property(props, drm_enum_property(DRM_PROP_ID_SCALING_MODE, "scaling mode", &[
+        (0, "None"),
+        (1, "Full"),
+        (2, "Center"),
+        (3, "Full aspect"),
+    ]));
+    drm_register_property(props, drm_bitmask_property(DRM_PROP_ID_ROTATION, "rotation", &[
+        (DRM_ROTATE_0, "rotate-0"),
+        (DRM_ROTATE_90, "rotate-90"),
+        (DRM_ROTATE_180, "rotate-180"),
+        (DRM_ROTATE_270, "rotate-270"),
+        (DRM_REFLECT_X, "reflect-x"),
+        (DRM_REFLECT_Y, "reflect-y"),
+    ]));
+    drm_register_property(props, drm_enum_property(DRM_PROP_ID_CONTENT_TYPE, "content type", &[
+        (0, "No Data"),
+        (1, "Graphics"),
+        (2, "Photo"),
+        (3, "Cinema"),
+        (4, "Game"),
+    ]));
+}
+
+fn drm_validate_property_value(prop: &DrmProperty, value: u64) -> bool {
+    match prop.prop_type {
+        DRM_PROP_TYPE_ENUM => {
+            (0..prop.enum_count as usize).any(|i| prop.enum_entries[i].value == value)
+        }
+        DRM_PROP_TYPE_BITMASK => {
+            let mask: u64 = (0..prop.enum_count as usize)
+                .fold(0u64, |acc, i| acc | prop.enum_entries[i].value);
+            value & !mask == 0
+        }
+        DRM_PROP_TYPE_RANGE => value >= prop.range_min && value <= prop.range_max,
+        DRM_PROP_TYPE_SIGNED_RANGE => {
+            let v = value as i64;
+            v >= prop.range_min as i64 && v <= prop.range_max as i64
+        }
+        DRM_PROP_TYPE_BLOB => true,
+        _ => false,
+    }
+}
+
+fn drm_find_value_slot(props: &DrmPropertyManager, object_type: u32, object_id: u32, property_id: u32) -> Option<usize> {
+    props.values.iter().position(|v| {
+        v.in_use && v.object_type == object_type && v.object_id == object_id && v.property_id == property_id
+    })
+}
+
+// Reads a staged property value, falling back to `default` when nothing has
+// been set for this object/property pair yet.
+fn drm_get_property_value(object_type: u32, object_id: u32, property_id: u32, default: u64) -> u64 {
+    unsafe {
+        match drm_find_value_slot(&DRM_PROPS, object_type, object_id, property_id) {
+            Some(idx) => DRM_PROPS.values[idx].value,
+            None => default,
+        }
+    }
+}
+
+// Attach/update a property value on a DRM object.
+#[no_mangle]
+pub extern "C" fn aurora_drm_object_set_property(object_type: u32, object_id: u32, property_id: u32, value: u64) -> i32 {
+    unsafe {
+        if property_id == 0 || property_id as usize > DRM_MAX_PROPERTIES {
+            return -1;
+        }
+
+        let prop = match DRM_PROPS.properties[(property_id - 1) as usize] {
+            Some(p) => p,
+            None => return -2, // unknown property
+        };
+
+        if !drm_validate_property_value(&prop, value) {
+            return -3; // value out of range / not in enum set
+        }
+
+        if let Some(idx) = drm_find_value_slot(&DRM_PROPS, object_type, object_id, property_id) {
+            DRM_PROPS.values[idx].value = value;
+            return 0;
+        }
+
+        for slot in DRM_PROPS.values.iter_mut() {
+            if !slot.in_use {
+                *slot = DrmPropertyValue { object_type, object_id, property_id, value, in_use: true };
+                return 0;
+            }
+        }
+
+        -4 // value table full
+    }
+}
+
+// Read back a property's currently staged value.
+#[no_mangle]
+pub extern "C" fn aurora_drm_object_get_property(object_type: u32, object_id: u32, property_id: u32, value_out: *mut u64) -> i32 {
+    if value_out.is_null() {
+        return -1;
+    }
+
+    unsafe {
+        match drm_find_value_slot(&DRM_PROPS, object_type, object_id, property_id) {
+            Some(idx) => {
+                *value_out = DRM_PROPS.values[idx].value;
+                0
+            }
+            None => -2, // not set
+        }
+    }
+}
+
+// Framebuffer modifier encoding: vendor in the top 8 bits, a vendor-defined
+// format code in the low 56 bits (mirrors the upstream DRM_FORMAT_MOD_* /
+// fourcc_mod_code scheme).
+const DRM_MODIFIER_VENDOR_NONE: u8 = 0;
+const DRM_MODIFIER_VENDOR_GENERIC: u8 = 1;
+const DRM_MODIFIER_VENDOR_ARM: u8 = 2;
+
+const fn drm_fourcc_mod_code(vendor: u8, val: u64) -> u64 {
+    ((vendor as u64) << 56) | (val & 0x00FF_FFFF_FFFF_FFFF)
+}
+
+pub const DRM_FORMAT_MOD_LINEAR: u64 = drm_fourcc_mod_code(DRM_MODIFIER_VENDOR_NONE, 0);
+pub const DRM_FORMAT_MOD_GENERIC_16X16_TILED: u64 = drm_fourcc_mod_code(DRM_MODIFIER_VENDOR_GENERIC, 1);
+
+// ARM AFBC sub-parameter bits, packed into the low 56 bits of an AFBC modifier.
+pub const AFBC_FORMAT_MOD_BLOCK_SIZE_16X16: u64 = 1 << 0;
+pub const AFBC_FORMAT_MOD_BLOCK_SIZE_32X8: u64 = 1 << 1;
+pub const AFBC_FORMAT_MOD_YTR: u64 = 1 << 4; // luma/chroma transform
+pub const AFBC_FORMAT_MOD_SPLIT: u64 = 1 << 5; // payload split across two halves
+pub const AFBC_FORMAT_MOD_TILED: u64 = 1 << 6; // body laid out behind a tiled header
+
+const fn drm_format_mod_arm_afbc(afbc_flags: u64) -> u64 {
+    drm_fourcc_mod_code(DRM_MODIFIER_VENDOR_ARM, afbc_flags)
+}
+
+pub const DRM_FORMAT_MOD_ARM_AFBC_16X16: u64 =
+    drm_format_mod_arm_afbc(AFBC_FORMAT_MOD_BLOCK_SIZE_16X16);
+pub const DRM_FORMAT_MOD_ARM_AFBC_16X16_YTR: u64 =
+    drm_format_mod_arm_afbc(AFBC_FORMAT_MOD_BLOCK_SIZE_16X16 | AFBC_FORMAT_MOD_YTR);
+
+// Whether `plane` has advertised support for scanning out `format` with
+// `modifier`. This driver doesn't track per-format modifier sets, only a
+// flat per-plane modifier list, so any advertised modifier is assumed valid
+// for any of the plane's advertised formats.
+fn drm_plane_supports_modifier(plane: &Plane, format: PixelFormat, modifier: u64) -> bool {
+    let format_ok = (0..plane.format_count as usize).any(|i| plane.formats[i] == format);
+    let modifier_ok = (0..plane.modifier_count as usize).any(|i| plane.modifiers[i] == modifier);
+    format_ok && modifier_ok
+}
+
+// Minimal per-framebuffer (format, modifier) record. `aurora_drm_create_framebuffer`
+// used to hand out bare IDs with nothing tracked behind them; planes now need
+// to know a bound framebuffer's format/modifier to validate scanout support.
+const DRM_MAX_FRAMEBUFFERS: usize = 32;
+
+#[derive(Debug, Clone, Copy)]
+struct FbRecord {
+    id: u32,
+    format: PixelFormat,
+    modifier: u64,
+    in_use: bool,
+}
+
+static mut DRM_FRAMEBUFFERS: [FbRecord; DRM_MAX_FRAMEBUFFERS] = [FbRecord {
+    id: 0,
+    format: PixelFormat::RGBA8888,
+    modifier: 0,
+    in_use: false,
+}; DRM_MAX_FRAMEBUFFERS];
+
+fn drm_lookup_framebuffer(fb_id: u32) -> Option<FbRecord> {
+    unsafe { DRM_FRAMEBUFFERS.iter().find(|r| r.in_use && r.id == fb_id).copied() }
+}
+
+// Query whether `plane_id` can scan out `format`/`modifier`.
+#[no_mangle]
+pub extern "C" fn aurora_drm_plane_supports_modifier(plane_id: u32, format: PixelFormat, modifier: u64) -> i32 {
+    unsafe {
+        if plane_id == 0 || plane_id > DRM_STATE.plane_count {
+            return -1;
+        }
+        let plane_idx = (plane_id - 1) as usize;
+        if plane_idx >= DRM_MAX_PLANES {
+            return -1;
+        }
+
+        if drm_plane_supports_modifier(&DRM_STATE.planes[plane_idx], format, modifier) {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+// Plane type values (matches the seeded `DRM_PROP_ID_PLANE_TYPE` enum).
+const DRM_PLANE_TYPE_PRIMARY: u32 = 1;
+const DRM_PLANE_TYPE_OVERLAY: u32 = 2;
+const DRM_PLANE_TYPE_CURSOR: u32 = 3;
+
+// Atomic state check: validates CRTC mode consistency, plane routing/bounds,
+// plane format/modifier support, and connector->encoder->crtc routing legality
+// before any of it is allowed to land in `DRM_STATE`. Returns 0 if the state
+// is internally consistent, otherwise a negative code identifying which
+// check failed.
+fn drm_atomic_check(state: &AtomicState) -> i32 {
+    // Every enabled CRTC needs a bound framebuffer and an internally
+    // consistent mode (clock/htotal/vtotal nonzero, sync window inside the
+    // total).
+    for c in 0..state.crtc_count as usize {
+        let crtc = &state.crtcs[c];
+        if !crtc.enabled {
+            continue;
+        }
+        if crtc.fb_id == 0 {
+            return -10;
+        }
+        let mode = &crtc.mode;
+        if mode.clock == 0 || mode.htotal == 0 || mode.vtotal == 0 {
+            return -10;
+        }
+        if !(mode.hsync_start < mode.hsync_end && mode.hsync_end <= mode.htotal) {
+            return -10;
+        }
+        if !(mode.vsync_start < mode.vsync_end && mode.vsync_end <= mode.vtotal) {
+            return -10;
+        }
+    }
+
+    // Active planes (bound to a framebuffer) must route to a CRTC that's
+    // actually in their possible_crtcs mask, land fully inside that CRTC's
+    // visible area, and be able to scan out their bound framebuffer's
+    // format/modifier.
+    for p in 0..state.plane_count as usize {
+        let plane = &state.planes[p];
+        if plane.fb_id == 0 {
+            continue;
+        }
+
+        if plane.crtc_id == 0 || plane.crtc_id > 32 {
+            return -11;
+        }
+        if plane.possible_crtcs & (1 << (plane.crtc_id - 1)) == 0 {
+            return -11;
+        }
+
+        let crtc = (0..state.crtc_count as usize)
+            .map(|c| &state.crtcs[c])
+            .find(|c| c.id == plane.crtc_id);
+        let crtc = match crtc {
+            Some(c) => c,
+            None => return -11,
+        };
+
+        // src_w/src_h are 16.16 fixed point; only the integer part matters
+        // for bounds checking against the CRTC's visible area.
+        let src_w = plane.src_w >> 16;
+        let src_h = plane.src_h >> 16;
+        if src_w == 0 || src_h == 0 || plane.crtc_w == 0 || plane.crtc_h == 0 {
+            return -12;
+        }
+        if plane.crtc_x < 0 || plane.crtc_y < 0 {
+            return -12;
+        }
+        let right = plane.crtc_x as u32 + plane.crtc_w;
+        let bottom = plane.crtc_y as u32 + plane.crtc_h;
+        if right > crtc.mode.hdisplay as u32 || bottom > crtc.mode.vdisplay as u32 {
+            return -12;
+        }
+
+        match drm_lookup_framebuffer(plane.fb_id) {
+            Some(fb) if drm_plane_supports_modifier(plane, fb.format, fb.modifier) => {}
+            _ => return -4,
+        }
+    }
+
+    // Connector -> encoder -> CRTC routing must be legal per the encoder's
+    // possible_crtcs (and, for cloned outputs, possible_clones).
+    for co in 0..state.connector_count as usize {
+        let connector = &state.connectors[co];
+        if connector.encoder_id == 0 {
+            continue;
+        }
+
+        let encoder = (0..state.encoder_count as usize)
+            .map(|e| &state.encoders[e])
+            .find(|e| e.id == connector.encoder_id);
+        let encoder = match encoder {
+            Some(e) => e,
+            None => return -13,
+        };
+
+        if encoder.crtc_id == 0 {
+            continue; // encoder exists but isn't routed to a CRTC yet
+        }
+
+        let crtc_idx = (0..state.crtc_count as usize).find(|&c| state.crtcs[c].id == encoder.crtc_id);
+        let crtc_idx = match crtc_idx {
+            Some(c) => c,
+            None => return -13,
+        };
+
+        if encoder.possible_crtcs & (1 << crtc_idx) == 0 {
+            return -13;
+        }
+    }
+
+    // MST virtual connectors need a live time-slot payload before they're
+    // allowed to come up; an encoder without allocated bandwidth on the
+    // shared link is not a valid display.
+    unsafe {
+        for co in 0..state.connector_count as usize {
+            let connector = &state.connectors[co];
+            if connector.encoder_id == 0 {
+                continue;
+            }
+            let is_mst_sink = MST_PORTS.iter().any(|p| p.in_use && p.virtual_connector_id == connector.id);
+            if is_mst_sink {
+                let has_payload = MST_PORTS.iter().any(|p| {
+                    p.in_use && p.virtual_connector_id == connector.id && p.time_slots > 0
+                });
+                if !has_payload {
+                    return -14;
+                }
+            }
+        }
+    }
+
+    0
+}
+
+// Writeback connector type (mirrors DRM_MODE_CONNECTOR_WRITEBACK) for
+// headless capture targets that don't scan out to a physical display.
+const DRM_CONNECTOR_TYPE_WRITEBACK: u32 = 18;
+const DRM_ENCODER_TYPE_WRITEBACK: u32 = 5;
+
+const DRM_MAX_WRITEBACK: usize = DRM_MAX_CRTCS;
+
+// One pending writeback attachment per CRTC. `fence_ptr` is the caller's
+// completion-fence cell, set to 1 (and the slot retired) by the next commit
+// that has this CRTC enabled. Framebuffers are tracked by ID only, so that
+// commit copies no pixel data into `fb_id`; it flushes caches and signals.
+#[derive(Debug, Clone, Copy)]
+struct WritebackTarget {
+    crtc_id: u32,
+    connector_id: u32,
+    fb_id: u32,
+    fence_ptr: *mut i32,
+    pending: bool,
+}
+
+static mut DRM_WRITEBACK: [WritebackTarget; DRM_MAX_WRITEBACK] = [WritebackTarget {
+    crtc_id: 0,
+    connector_id: 0,
+    fb_id: 0,
+    fence_ptr: ptr::null_mut(),
+    pending: false,
+}; DRM_MAX_WRITEBACK];
+
+// Attach a writeback target to a CRTC: the next atomic commit that has this
+// CRTC enabled flushes caches and sets `*out_fence` to 1. No composited pixel
+// data is copied into `fb_id`.
+#[no_mangle]
+pub extern "C" fn aurora_drm_writeback_attach(crtc_id: u32, fb_id: u32, out_fence: *mut i32) -> i32 {
+    unsafe {
+        if crtc_id == 0 || crtc_id > DRM_STATE.crtc_count {
+            return -1;
+        }
+        let crtc_idx = (crtc_id - 1) as usize;
+        if crtc_idx >= DRM_MAX_CRTCS {
+            return -1;
+        }
+
+        let connector_idx = DRM_STATE.connector_count as usize;
+        let encoder_idx = DRM_STATE.encoder_count as usize;
+        if connector_idx >= DRM_MAX_CONNECTORS || encoder_idx >= DRM_MAX_ENCODERS {
+            return -2;
+        }
+
+        let connector_id = connector_idx as u32 + 1;
+        let encoder_id = encoder_idx as u32 + 1;
+
+        DRM_STATE.encoders[encoder_idx] = Encoder {
+            id: encoder_id,
+            encoder_type: DRM_ENCODER_TYPE_WRITEBACK,
+            crtc_id,
+            possible_crtcs: 1 << crtc_idx,
+            possible_clones: 0,
+        };
+        DRM_STATE.encoder_count += 1;
+
+        DRM_STATE.connectors[connector_idx] = Connector {
+            id: connector_id,
+            connector_type: DRM_CONNECTOR_TYPE_WRITEBACK,
+            connector_type_id: connector_id,
+            status: ConnectorStatus::Unknown, // writeback targets aren't hotplug-detected
+            width_mm: 0,
+            height_mm: 0,
+            modes: core::array::from_fn(|_| DisplayMode {
+                clock: 0, hdisplay: 0, hsync_start: 0, hsync_end: 0, htotal: 0,
+                vdisplay: 0, vsync_start: 0, vsync_end: 0, vtotal: 0, flags: 0, name: [0; 32],
+            }),
+            mode_count: 0,
+            encoder_id,
+        };
+        DRM_STATE.connector_count += 1;
+
+        let slot_idx = DRM_WRITEBACK.iter().position(|s| !s.pending);
+        let idx = match slot_idx {
+            Some(i) => i,
+            None => return -3, // writeback table full
+        };
+
+        DRM_WRITEBACK[idx] = WritebackTarget { crtc_id, connector_id, fb_id, fence_ptr: out_fence, pending: true };
+
+        if !out_fence.is_null() {
+            *out_fence = 0; // not yet signalled
+        }
+    }
+
+    0
+}
+
+// EDID (VESA E-EDID) base/extension block size and the fixed 8-byte header
+// every base block must start with.
+const EDID_BLOCK_SIZE: usize = 128;
+const EDID_HEADER: [u8; 8] = [0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00];
+
+// Established Timings I/II bitmap (bytes 35-37): (byte offset from 35, bit,
+// width, height, refresh Hz) for each VESA/DMT timing the bit represents.
+const EDID_ESTABLISHED_TIMINGS: [(usize, u8, u16, u16, u32); 16] = [
+    (0, 7, 720, 400, 70),
+    (0, 6, 720, 400, 88),
+    (0, 5, 640, 480, 60),
+    (0, 4, 640, 480, 67),
+    (0, 3, 640, 480, 72),
+    (0, 2, 640, 480, 75),
+    (0, 1, 800, 600, 56),
+    (0, 0, 800, 600, 60),
+    (1, 7, 800, 600, 72),
+    (1, 6, 800, 600, 75),
+    (1, 5, 832, 624, 75),
+    (1, 3, 1024, 768, 60),
+    (1, 2, 1024, 768, 70),
+    (1, 1, 1024, 768, 75),
+    (1, 0, 1280, 1024, 75),
+    (2, 7, 1152, 870, 75),
+];
+
+fn edid_push_mode(connector: &mut Connector, count: &mut usize, mode: DisplayMode) {
+    if *count < DRM_MAX_MODES {
+        connector.modes[*count] = mode;
+        *count += 1;
+    }
+}
+
+// Standard Timing descriptor (2 bytes): X resolution is coarsely encoded,
+// aspect ratio picks the height, and refresh is offset by 60Hz. `01 01` is
+// the "unused" sentinel.
+fn edid_decode_standard_timing(b1: u8, b2: u8) -> Option<DisplayMode> {
+    if b1 == 0x01 && b2 == 0x01 {
+        return None;
+    }
+    if b1 == 0 {
+        return None;
+    }
+
+    let width = (b1 as u16 + 31) * 8;
+    let aspect = (b2 >> 6) & 0x3;
+    let refresh = (b2 & 0x3f) as u32 + 60;
+    let height = match aspect {
+        0 => width * 10 / 16,
+        1 => width * 3 / 4,
+        2 => width * 4 / 5,
+        _ => width * 9 / 16,
+    };
+
+    Some(create_display_mode(width, height, refresh, "STD"))
+}
+
+// Detailed Timing Descriptor (18 bytes): unlike the established/standard
+// shorthand timings above, this carries the display's actual reported
+// blanking/sync timings rather than synthetic VESA porches.
+fn edid_decode_detailed_timing(desc: &[u8]) -> Option<DisplayMode> {
+    if desc.len() < 18 {
+        return None;
+    }
+
+    let pixel_clock_10khz = desc[0] as u32 | ((desc[1] as u32) << 8);
+    if pixel_clock_10khz == 0 {
+        // A zero pixel clock means this is a display descriptor (monitor
+        // name, serial number, etc), not a timing descriptor.
+        return None;
+    }
+
+    let hactive = desc[2] as u16 | (((desc[4] >> 4) as u16) << 8);
+    let hblank = desc[3] as u16 | (((desc[4] & 0x0f) as u16) << 8);
+    let vactive = desc[5] as u16 | (((desc[7] >> 4) as u16) << 8);
+    let vblank = desc[6] as u16 | (((desc[7] & 0x0f) as u16) << 8);
+
+    if hactive == 0 || vactive == 0 {
+        return None;
+    }
+
+    let hsync_offset = desc[8] as u16 | (((desc[11] >> 6) as u16) << 8);
+    let hsync_width = desc[9] as u16 | ((((desc[11] >> 4) & 0x3) as u16) << 8);
+    let vsync_offset = (desc[10] >> 4) as u16 | ((((desc[11] >> 2) & 0x3) as u16) << 4);
+    let vsync_width = (desc[10] & 0x0f) as u16 | (((desc[11] & 0x3) as u16) << 4);
+
+    let hsync_start = hactive + hsync_offset;
+    let vsync_start = vactive + vsync_offset;
+
+    let mut mode = DisplayMode {
+        clock: pixel_clock_10khz * 10,
+        hdisplay: hactive,
+        hsync_start,
+        hsync_end: hsync_start + hsync_width,
+        htotal: hactive + hblank,
+        vdisplay: vactive,
+        vsync_start,
+        vsync_end: vsync_start + vsync_width,
+        vtotal: vactive + vblank,
+        flags: 0,
+        name: [0; 32],
+    };
+
+    let name = b"EDID-DTD\0";
+    let copy_len = core::cmp::min(name.len(), 31);
+    mode.name[..copy_len].copy_from_slice(&name[..copy_len]);
+
+    Some(mode)
+}
+
+// Parse a (possibly multi-block) EDID and populate `Connector.modes` from
+// the established timings bitmap, standard timing descriptors, the base
+// block's four detailed timing descriptors, and any detailed timings found
+// in CEA-861 extension blocks. Returns 0 on success, a negative error code
+// on a malformed base block.
+#[no_mangle]
+pub extern "C" fn aurora_drm_parse_edid(connector_id: u32, edid: *const u8, len: u32) -> i32 {
+    if edid.is_null() || (len as usize) < EDID_BLOCK_SIZE {
+        return -1;
+    }
+
+    unsafe {
+        if connector_id == 0 || connector_id > DRM_STATE.connector_count {
+            return -1;
+        }
+
+        let connector_idx = (connector_id - 1) as usize;
+        if connector_idx >= DRM_MAX_CONNECTORS {
+            return -1;
+        }
+
+        let block = slice::from_raw_parts(edid, EDID_BLOCK_SIZE);
+
+        if block[0..8] != EDID_HEADER {
+            return -2; // bad header
+        }
+
+        let sum: u32 = block.iter().map(|&b| b as u32).sum();
+        if sum % 256 != 0 {
+            return -3; // checksum failure
+        }
+
+        let width_cm = block[21];
+        let height_cm = block[22];
+
+        let connector = &mut DRM_STATE.connectors[connector_idx];
+        connector.width_mm = width_cm as u32 * 10;
+        connector.height_mm = height_cm as u32 * 10;
+
+        let mut count: usize = 0;
+
+        for &(byte_idx, bit, w, h, refresh) in EDID_ESTABLISHED_TIMINGS.iter() {
+            if block[35 + byte_idx] & (1 << bit) != 0 {
+                edid_push_mode(connector, &mut count, create_display_mode(w, h, refresh, "EST"));
+            }
+        }
+
+        for i in 0..8 {
+            let off = 38 + i * 2;
+            if let Some(mode) = edid_decode_standard_timing(block[off], block[off + 1]) {
+                edid_push_mode(connector, &mut count, mode);
+            }
+        }
+
+        for &off in &[54usize, 72, 90, 108] {
+            if let Some(mode) = edid_decode_detailed_timing(&block[off..off + 18]) {
+                edid_push_mode(connector, &mut count, mode);
+            }
+        }
+
+        let ext_count = block[126] as usize;
+        if ext_count > 0 && (len as usize) >= EDID_BLOCK_SIZE * (1 + ext_count) {
+            let all = slice::from_raw_parts(edid, EDID_BLOCK_SIZE * (1 + ext_count));
+
+            for e in 0..ext_count {
+                let ext = &all[EDID_BLOCK_SIZE * (1 + e)..EDID_BLOCK_SIZE * (2 + e)];
+
+                match ext[0] {
+                    0x02 => {
+                        // CEA-861 extension: byte 2 holds the offset of the
+                        // first detailed timing descriptor; everything from
+                        // there to byte 127 is a run of 18-byte DTDs, same
+                        // layout as the base block's.
+                        let dtd_start = ext[2] as usize;
+                        if dtd_start >= 4 {
+                            let mut off = dtd_start;
+                            while off + 18 <= EDID_BLOCK_SIZE - 1 {
+                                if let Some(mode) = edid_decode_detailed_timing(&ext[off..off + 18]) {
+                                    edid_push_mode(connector, &mut count, mode);
+                                }
+                                off += 18;
+                            }
+                        }
+                    }
+                    0x70 => {
+                        // DisplayID extension: its tagged data-block layout
+                        // is a different format entirely from the rest of
+                        // this parser. Acknowledged so it doesn't get
+                        // mistaken for a CEA block, but contributes no
+                        // modes here.
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        connector.mode_count = count as u32;
+    }
+
+    0
+}
+
 // Helper function to create display mode
 fn create_display_mode(width: u16, height: u16, refresh: u32, name: &str) -> DisplayMode {
     let mut mode = DisplayMode {
@@ -278,6 +1061,8 @@ pub extern "C" fn aurora_drm_init() -> i32 {
             fb_id: 0,
             gamma_size: 256,
             enabled: true,
+            cursor_max_w: 64,
+            cursor_max_h: 64,
         };
         DRM_STATE.crtc_count = 1;
         
@@ -314,6 +1099,18 @@ pub extern "C" fn aurora_drm_init() -> i32 {
             crtc_w: 1920,
             crtc_h: 1080,
             fb_id: 0,
+            crtc_id: 1,
+            modifiers: [
+                DRM_FORMAT_MOD_LINEAR,
+                DRM_FORMAT_MOD_GENERIC_16X16_TILED,
+                DRM_FORMAT_MOD_ARM_AFBC_16X16,
+                DRM_FORMAT_MOD_ARM_AFBC_16X16_YTR,
+                0,
+                0,
+                0,
+                0,
+            ],
+            modifier_count: 4,
         };
         DRM_STATE.plane_count = 1;
         
@@ -347,7 +1144,14 @@ pub extern "C" fn aurora_drm_init() -> i32 {
             possible_clones: 0,
         };
         DRM_STATE.encoder_count = 1;
-        
+
+        drm_seed_standard_properties(&mut DRM_PROPS);
+        aurora_drm_object_set_property(DrmObjectType::Connector as u32, 1, DRM_PROP_ID_DPMS, 0); // On
+        aurora_drm_object_set_property(DrmObjectType::Connector as u32, 1, DRM_PROP_ID_CONTENT_TYPE, 0); // No Data
+        aurora_drm_object_set_property(DrmObjectType::Plane as u32, 1, DRM_PROP_ID_PLANE_TYPE, 1); // Primary
+        aurora_drm_object_set_property(DrmObjectType::Plane as u32, 1, DRM_PROP_ID_SCALING_MODE, 0); // None
+        aurora_drm_object_set_property(DrmObjectType::Plane as u32, 1, DRM_PROP_ID_ROTATION, DRM_ROTATE_0);
+
         aur_debug_print(b"Aurora DRM/KMS initialized successfully\n\0".as_ptr());
     }
     
@@ -397,20 +1201,27 @@ pub extern "C" fn aurora_drm_modeset(crtc_id: u32, fb_id: u32, x: u32, y: u32,
 // Create framebuffer
 #[no_mangle]
 pub extern "C" fn aurora_drm_create_framebuffer(width: u32, height: u32, format: PixelFormat,
-                                               handles: *const u32, pitches: *const u32,
-                                               offsets: *const u32) -> u32 {
+                                               modifier: u64, handles: *const u32,
+                                               pitches: *const u32, offsets: *const u32) -> u32 {
     static mut FB_ID_COUNTER: u32 = 1;
-    
+
     unsafe {
         let fb_id = FB_ID_COUNTER;
+
+        let slot = DRM_FRAMEBUFFERS.iter_mut().find(|r| !r.in_use);
+        let slot = match slot {
+            Some(s) => s,
+            None => return 0, // framebuffer table full
+        };
+        *slot = FbRecord { id: fb_id, format, modifier, in_use: true };
         FB_ID_COUNTER += 1;
-        
+
         // In a real implementation, this would allocate and register the framebuffer
-        // For now, we just return a unique ID
-        
-        aur_debug_print(b"Created framebuffer %d: %dx%d format=%d\n\0".as_ptr(), 
+        // For now, we just track (format, modifier) and return a unique ID
+
+        aur_debug_print(b"Created framebuffer %d: %dx%d format=%d\n\0".as_ptr(),
                        fb_id, width, height, format as u32);
-        
+
         fb_id
     }
 }
@@ -423,26 +1234,90 @@ pub extern "C" fn aurora_drm_atomic_commit(state: *const AtomicState, flags: u32
             return -1;
         }
         
-        let new_state = ptr::read(state);
-        
-        // Test-only commit
+        let mut new_state = ptr::read(state);
+
+        // Consume staged plane rotation before validating: a 90/270
+        // rotation swaps the on-screen footprint of the plane's scanout
+        // rectangle, and drm_atomic_check's CRTC-bounds check needs to see
+        // the post-swap footprint, not the pre-swap one, or a plane that
+        // only fits its CRTC after rotating would pass the check and then
+        // land out of bounds once the swap below was applied.
+        for i in 0..new_state.plane_count as usize {
+            let plane_id = new_state.planes[i].id;
+            let rotation = drm_get_property_value(DrmObjectType::Plane as u32, plane_id, DRM_PROP_ID_ROTATION, DRM_ROTATE_0);
+            if rotation & (DRM_ROTATE_90 | DRM_ROTATE_270) != 0 {
+                let plane = &mut new_state.planes[i];
+                mem::swap(&mut plane.crtc_w, &mut plane.crtc_h);
+            }
+        }
+
+        // Validate before touching anything else: a failing check must
+        // leave DRM_STATE untouched, whether this is a real commit or
+        // test-only.
+        let check = drm_atomic_check(&new_state);
+        if check != 0 {
+            return check;
+        }
+
+        // Test-only commit: validation is all this flag asks for.
         if flags & 0x100 != 0 {
-            // Validate the state without applying
             return 0;
         }
-        
+
         // Wait for vblank if requested
         if flags & 0x200 != 0 {
             aur_fb_vsync_wait();
         }
-        
+
+        // Consume staged DPMS: a connector set to anything other than On
+        // (Standby, Suspend or Off) drops the CRTC it's routed through so the
+        // rest of the pipeline treats it as blanked.
+        for i in 0..new_state.connector_count as usize {
+            let connector = &new_state.connectors[i];
+            let dpms = drm_get_property_value(DrmObjectType::Connector as u32, connector.id, DRM_PROP_ID_DPMS, 0);
+            if dpms != 0 {
+                let crtc_id = (0..new_state.encoder_count as usize)
+                    .find(|&e| new_state.encoders[e].id == connector.encoder_id)
+                    .map(|e| new_state.encoders[e].crtc_id);
+                if let Some(crtc_id) = crtc_id {
+                    for c in 0..new_state.crtc_count as usize {
+                        if new_state.crtcs[c].id == crtc_id {
+                            new_state.crtcs[c].enabled = false;
+                        }
+                    }
+                }
+            }
+        }
+
         // Apply the new state
         DRM_STATE = new_state;
-        
+
         // Ensure all GPU operations complete
         aur_fb_gpu_barrier();
         aur_fb_mem_fence();
-        
+
+        // Retire any attached writeback targets whose CRTC is enabled: flush
+        // caches and signal their completion fence. No pixel data is copied
+        // into the writeback framebuffer.
+        for slot in DRM_WRITEBACK.iter_mut() {
+            if !slot.pending {
+                continue;
+            }
+            let crtc_enabled = (0..DRM_STATE.crtc_count as usize)
+                .any(|c| DRM_STATE.crtcs[c].id == slot.crtc_id && DRM_STATE.crtcs[c].enabled);
+            if !crtc_enabled {
+                continue;
+            }
+
+            aur_fb_cache_flush(0);
+            aur_debug_print(b"Writeback crtc %d -> fb %d\n\0".as_ptr(), slot.crtc_id, slot.fb_id);
+
+            if !slot.fence_ptr.is_null() {
+                *slot.fence_ptr = 1; // signalled
+            }
+            slot.pending = false;
+        }
+
         aur_debug_print(b"Atomic commit completed\n\0".as_ptr());
     }
     
@@ -514,8 +1389,26 @@ pub extern "C" fn aurora_drm_update_plane(plane_id: u32, crtc_id: u32, fb_id: u3
         if plane_idx >= DRM_MAX_PLANES {
             return -1;
         }
-        
+
+        if fb_id != 0 {
+            match drm_lookup_framebuffer(fb_id) {
+                Some(fb) if drm_plane_supports_modifier(&DRM_STATE.planes[plane_idx], fb.format, fb.modifier) => {}
+                Some(_) => return -4, // plane doesn't support this format/modifier combo
+                None => return -5,    // unknown framebuffer
+            }
+        }
+
+        if crtc_id != 0 {
+            if crtc_id > DRM_STATE.crtc_count || crtc_id > 32 {
+                return -6;
+            }
+            if DRM_STATE.planes[plane_idx].possible_crtcs & (1 << (crtc_id - 1)) == 0 {
+                return -6; // CRTC not in this plane's possible_crtcs mask
+            }
+        }
+
         // Update plane configuration
+        DRM_STATE.planes[plane_idx].crtc_id = crtc_id;
         DRM_STATE.planes[plane_idx].crtc_x = crtc_x;
         DRM_STATE.planes[plane_idx].crtc_y = crtc_y;
         DRM_STATE.planes[plane_idx].crtc_w = crtc_w;
@@ -534,6 +1427,334 @@ pub extern "C" fn aurora_drm_update_plane(plane_id: u32, crtc_id: u32, fb_id: u3
     0 // Success
 }
 
+// Locate this CRTC's dedicated cursor plane, lazily allocating one the
+// first time a cursor is set or moved on it (mirroring how hardware exposes a fixed
+// cursor plane per CRTC without requiring a client to discover it first).
+fn drm_find_or_create_cursor_plane(crtc_idx: usize) -> Option<usize> {
+    unsafe {
+        let crtc_id = DRM_STATE.crtcs[crtc_idx].id;
+
+        for i in 0..DRM_STATE.plane_count as usize {
+            let plane = &DRM_STATE.planes[i];
+            if plane.plane_type == DRM_PLANE_TYPE_CURSOR && plane.crtc_id == crtc_id {
+                return Some(i);
+            }
+        }
+
+        let idx = DRM_STATE.plane_count as usize;
+        if idx >= DRM_MAX_PLANES {
+            return None;
+        }
+
+        DRM_STATE.planes[idx] = Plane {
+            id: idx as u32 + 1,
+            plane_type: DRM_PLANE_TYPE_CURSOR,
+            possible_crtcs: 1 << crtc_idx,
+            formats: [PixelFormat::RGBA8888; 16],
+            format_count: 2, // RGBA8888, BGRA8888
+            src_x: 0,
+            src_y: 0,
+            src_w: 0,
+            src_h: 0,
+            crtc_x: 0,
+            crtc_y: 0,
+            crtc_w: 0,
+            crtc_h: 0,
+            fb_id: 0,
+            crtc_id,
+            modifiers: [DRM_FORMAT_MOD_LINEAR, 0, 0, 0, 0, 0, 0, 0],
+            modifier_count: 1,
+        };
+        DRM_STATE.planes[idx].formats[1] = PixelFormat::BGRA8888;
+        DRM_STATE.plane_count += 1;
+
+        Some(idx)
+    }
+}
+
+// Program the hardware cursor plane for a CRTC without requiring a full
+// atomic commit (mirrors ast/vkms-style dedicated cursor fast paths).
+#[no_mangle]
+pub extern "C" fn aurora_drm_cursor_set(crtc_id: u32, fb_id: u32, width: u32, height: u32) -> i32 {
+    unsafe {
+        if crtc_id == 0 || crtc_id > DRM_STATE.crtc_count {
+            return -1;
+        }
+        let crtc_idx = (crtc_id - 1) as usize;
+        if crtc_idx >= DRM_MAX_CRTCS {
+            return -1;
+        }
+
+        let crtc = &DRM_STATE.crtcs[crtc_idx];
+        if width > crtc.cursor_max_w || height > crtc.cursor_max_h {
+            return -2; // exceeds advertised max cursor size for this CRTC
+        }
+
+        if fb_id != 0 {
+            match drm_lookup_framebuffer(fb_id) {
+                Some(fb) if fb.format == PixelFormat::RGBA8888 || fb.format == PixelFormat::BGRA8888 => {}
+                Some(_) => return -3, // cursor plane only scans out ARGB-class formats
+                None => return -4,    // unknown framebuffer
+            }
+        }
+
+        let plane_idx = match drm_find_or_create_cursor_plane(crtc_idx) {
+            Some(i) => i,
+            None => return -5, // out of plane slots
+        };
+
+        let plane = &mut DRM_STATE.planes[plane_idx];
+        plane.crtc_id = crtc_id;
+        plane.fb_id = fb_id;
+        plane.crtc_w = width;
+        plane.crtc_h = height;
+        plane.src_x = 0;
+        plane.src_y = 0;
+        plane.src_w = width << 16;
+        plane.src_h = height << 16;
+
+        aur_fb_vsync_wait();
+        aur_fb_mem_fence();
+    }
+
+    0
+}
+
+// Move an already-set hardware cursor, clamping it to the CRTC's visible
+// region while still allowing it to straddle the edges (negative positions,
+// or positions that push it past the right/bottom edge).
+#[no_mangle]
+pub extern "C" fn aurora_drm_cursor_move(crtc_id: u32, x: i32, y: i32) -> i32 {
+    unsafe {
+        if crtc_id == 0 || crtc_id > DRM_STATE.crtc_count {
+            return -1;
+        }
+        let crtc_idx = (crtc_id - 1) as usize;
+        if crtc_idx >= DRM_MAX_CRTCS {
+            return -1;
+        }
+
+        let plane_idx = match drm_find_or_create_cursor_plane(crtc_idx) {
+            Some(i) => i,
+            None => return -5,
+        };
+
+        if DRM_STATE.planes[plane_idx].fb_id == 0 {
+            return -6; // no cursor image set yet
+        }
+
+        let crtc_w = DRM_STATE.crtcs[crtc_idx].width as i32;
+        let crtc_h = DRM_STATE.crtcs[crtc_idx].height as i32;
+        let cursor_w = DRM_STATE.planes[plane_idx].crtc_w as i32;
+        let cursor_h = DRM_STATE.planes[plane_idx].crtc_h as i32;
+
+        let clamped_x = x.max(-(cursor_w - 1)).min(crtc_w - 1);
+        let clamped_y = y.max(-(cursor_h - 1)).min(crtc_h - 1);
+
+        DRM_STATE.planes[plane_idx].crtc_x = clamped_x;
+        DRM_STATE.planes[plane_idx].crtc_y = clamped_y;
+
+        aur_fb_vsync_wait();
+        aur_fb_mem_fence();
+    }
+
+    0
+}
+
+// DisplayPort MST (Multi-Stream Transport): fans a single physical DP
+// connector out to several downstream sinks behind a branch device, each
+// exposed as its own virtual Connector with its own timing and a slice of
+// the shared link's time-slot bandwidth.
+const DRM_CONNECTOR_TYPE_DISPLAYPORT: u32 = 10;
+const MST_MAX_PORTS: usize = 16;
+const MST_TOTAL_TIME_SLOTS: u8 = 63; // VESA DP MST: 64 slots total, slot 0 reserved
+const MST_SIM_SINKS_PER_BRANCH: u8 = 2; // this driver has no real AUX/sideband channel
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MstPortKind {
+    Branch,
+    Sink,
+}
+
+// One entry per discovered downstream port. `ref_count` is the number of
+// live holders of this port (here: how many probes have found it still
+// present); the port — and the virtual connector it backs — is torn down
+// once a hot-unplug drops it to zero.
+#[derive(Debug, Clone, Copy)]
+struct MstPort {
+    in_use: bool,
+    parent_connector_id: u32,
+    port_num: u8,
+    kind: MstPortKind,
+    ref_count: u32,
+    virtual_connector_id: u32,
+    time_slots: u8,
+}
+
+static mut MST_PORTS: [MstPort; MST_MAX_PORTS] = [MstPort {
+    in_use: false,
+    parent_connector_id: 0,
+    port_num: 0,
+    kind: MstPortKind::Sink,
+    ref_count: 0,
+    virtual_connector_id: 0,
+    time_slots: 0,
+}; MST_MAX_PORTS];
+
+fn mst_active_sink_ports(parent_connector_id: u32) -> usize {
+    unsafe {
+        MST_PORTS.iter()
+            .filter(|p| p.in_use && p.parent_connector_id == parent_connector_id && p.kind == MstPortKind::Sink)
+            .count()
+    }
+}
+
+// Rebalance the shared link's time slots evenly across all currently live
+// sinks behind `parent_connector_id`.
+fn mst_rebalance(parent_connector_id: u32) {
+    unsafe {
+        let count = mst_active_sink_ports(parent_connector_id);
+        if count == 0 {
+            return;
+        }
+        let per_sink = (MST_TOTAL_TIME_SLOTS as usize / count) as u8;
+        for port in MST_PORTS.iter_mut() {
+            if port.in_use && port.parent_connector_id == parent_connector_id && port.kind == MstPortKind::Sink {
+                port.time_slots = per_sink;
+            }
+        }
+    }
+}
+
+// Walk the (simulated) branch device hanging off `connector_id` and
+// allocate a virtual Connector + time-slot payload for each discovered
+// sink. Ports already known from a previous probe just pick up an extra
+// reference instead of a duplicate connector.
+#[no_mangle]
+pub extern "C" fn aurora_drm_mst_probe(connector_id: u32) -> i32 {
+    unsafe {
+        if connector_id == 0 || connector_id > DRM_STATE.connector_count {
+            return -1;
+        }
+
+        let mut discovered: u32 = 0;
+
+        for port_num in 0..MST_SIM_SINKS_PER_BRANCH {
+            let existing = MST_PORTS.iter().position(|p| {
+                p.in_use && p.parent_connector_id == connector_id && p.port_num == port_num
+            });
+
+            if let Some(idx) = existing {
+                MST_PORTS[idx].ref_count += 1;
+                discovered += 1;
+                continue;
+            }
+
+            let connector_idx = DRM_STATE.connector_count as usize;
+            let port_idx = MST_PORTS.iter().position(|p| !p.in_use);
+            let port_idx = match (connector_idx < DRM_MAX_CONNECTORS, port_idx) {
+                (true, Some(p)) => p,
+                _ => break, // out of connector or port slots; keep what we found so far
+            };
+
+            let virtual_connector_id = connector_idx as u32 + 1;
+
+            // Real hardware would read this sink's EDID over the MST
+            // sideband (AUX) channel; simulate a couple of plausible modes.
+            DRM_STATE.connectors[connector_idx] = Connector {
+                id: virtual_connector_id,
+                connector_type: DRM_CONNECTOR_TYPE_DISPLAYPORT,
+                connector_type_id: port_num as u32 + 1,
+                status: ConnectorStatus::Connected,
+                width_mm: 340,
+                height_mm: 190,
+                modes: core::array::from_fn(|i| match i {
+                    0 => create_display_mode(1920, 1080, 60, "MST-1920x1080@60"),
+                    1 => create_display_mode(1280, 720, 60, "MST-1280x720@60"),
+                    _ => DisplayMode {
+                        clock: 0, hdisplay: 0, hsync_start: 0, hsync_end: 0, htotal: 0,
+                        vdisplay: 0, vsync_start: 0, vsync_end: 0, vtotal: 0, flags: 0, name: [0; 32],
+                    },
+                }),
+                mode_count: 2,
+                encoder_id: 0, // routed on demand once the client enables this sink
+            };
+            DRM_STATE.connector_count += 1;
+
+            MST_PORTS[port_idx] = MstPort {
+                in_use: true,
+                parent_connector_id: connector_id,
+                port_num,
+                kind: MstPortKind::Sink,
+                ref_count: 1,
+                virtual_connector_id,
+                time_slots: 0, // assigned by mst_rebalance below
+            };
+
+            discovered += 1;
+        }
+
+        mst_rebalance(connector_id);
+
+        aur_debug_print(b"MST probe on connector %d: %d sink(s)\n\0".as_ptr(), connector_id, discovered);
+    }
+
+    0
+}
+
+// Hot-unplug notification for one MST branch port: drops its reference and,
+// once nothing else holds it, tears down the virtual connector it backed
+// and returns its time slots to the shared pool.
+#[no_mangle]
+pub extern "C" fn aurora_drm_mst_port_remove(connector_id: u32, port_num: u8) -> i32 {
+    unsafe {
+        let idx = MST_PORTS.iter().position(|p| {
+            p.in_use && p.parent_connector_id == connector_id && p.port_num == port_num
+        });
+        let idx = match idx {
+            Some(i) => i,
+            None => return -1,
+        };
+
+        if MST_PORTS[idx].ref_count > 1 {
+            MST_PORTS[idx].ref_count -= 1;
+            return 0;
+        }
+
+        let virtual_connector_id = MST_PORTS[idx].virtual_connector_id;
+        MST_PORTS[idx] = MstPort {
+            in_use: false,
+            parent_connector_id: 0,
+            port_num: 0,
+            kind: MstPortKind::Sink,
+            ref_count: 0,
+            virtual_connector_id: 0,
+            time_slots: 0,
+        };
+
+        if let Some(ci) = (0..DRM_STATE.connector_count as usize).find(|&i| DRM_STATE.connectors[i].id == virtual_connector_id) {
+            DRM_STATE.connectors[ci].status = ConnectorStatus::Disconnected;
+            DRM_STATE.connectors[ci].mode_count = 0;
+        }
+
+        mst_rebalance(connector_id);
+    }
+
+    0
+}
+
+// Report the DP MST time-slot payload currently assigned to a virtual
+// connector, or a negative error if `connector_id` isn't a live MST sink.
+#[no_mangle]
+pub extern "C" fn aurora_drm_mst_get_payload(connector_id: u32) -> i32 {
+    unsafe {
+        match MST_PORTS.iter().find(|p| p.in_use && p.virtual_connector_id == connector_id) {
+            Some(p) => p.time_slots as i32,
+            None => -1,
+        }
+    }
+}
+
 // Get resource counts
 #[no_mangle]
 pub extern "C" fn aurora_drm_get_resources(crtc_count: *mut u32, connector_count: *mut u32,
@@ -77,6 +77,37 @@ const NVME_ADMIN_ASYNC_EVENT: u8 = 0x0c;
 const NVME_ADMIN_NS_MGMT: u8 = 0x0d;
 const NVME_ADMIN_ACTIVATE_FW: u8 = 0x10;
 const NVME_ADMIN_DOWNLOAD_FW: u8 = 0x11;
+const NVME_ADMIN_KEEP_ALIVE: u8 = 0x18;
+
+const NVME_LOG_ERROR: u8 = 0x01;
+const NVME_LOG_SMART: u8 = 0x02;
+const NVME_LOG_CHANGED_NS: u8 = 0x04;
+
+// Async Event Request completion result dword: event type is bits 2:0,
+// log page identifier to re-read is bits 23:16 (NVMe base spec figure).
+const NVME_AEN_TYPE_ERROR: u8 = 0x00;
+const NVME_AEN_TYPE_SMART: u8 = 0x01;
+const NVME_AEN_TYPE_NOTICE: u8 = 0x02;
+
+// Upper bound on outstanding Async Event Requests we keep posted; real
+// controllers cap this at `aerl + 1` from Identify Controller, but this is
+// a reasonable ceiling regardless of what the controller reports.
+const NVME_AEN_POOL_SIZE: usize = 4;
+
+// Scratch size used to pull the log page a notice AEN points at. Covers
+// the SMART log; larger pages (e.g. the changed-namespace list) are read
+// truncated, which is enough to wake the host OS and let it re-fetch.
+const NVME_AEN_LOG_SCRATCH: usize = 512;
+
+// Depth of the pending-AEN queue. An AEN completion is reaped from
+// `process_cq` and can land at any time, so it's only decoded and stashed
+// there; sized the same as the AEN pool since that bounds how many can be
+// outstanding at once.
+const NVME_AEN_PENDING_SIZE: usize = NVME_AEN_POOL_SIZE;
+
+const NVME_FEAT_KEEP_ALIVE_TIMER: u8 = 0x0f;
+// Linux's NVME_DEFAULT_KATO: 5s keep-alive timeout when the host doesn't ask for a specific one.
+const NVME_DEFAULT_KATO_MS: u32 = 5000;
 const NVME_ADMIN_FORMAT_NVM: u8 = 0x80;
 const NVME_ADMIN_SECURITY_SEND: u8 = 0x81;
 const NVME_ADMIN_SECURITY_RECV: u8 = 0x82;
@@ -94,6 +125,17 @@ const NVME_CMD_RESV_REPORT: u8 = 0x0e;
 const NVME_CMD_RESV_ACQUIRE: u8 = 0x11;
 const NVME_CMD_RESV_RELEASE: u8 = 0x15;
 
+// Optional NVM Command Support bits (NvmeIdCtrl::oncs) gating discard() and
+// write_zeroes() so they're only sent when the controller advertises them.
+const NVME_ONCS_DSM: u16 = 1 << 2;
+const NVME_ONCS_WRITE_ZEROES: u16 = 1 << 3;
+
+// cdw11 Attribute-Deallocate bit for the Dataset Management command.
+const NVME_DSM_ATTR_DEALLOCATE: u32 = 1 << 2;
+// NR (number of ranges) is a 0-based 8-bit field, so at most 256 ranges
+// fit in a single DSM command.
+const NVME_DSM_MAX_RANGES: usize = 256;
+
 // NVMe command structure
 #[repr(C, packed)]
 #[derive(Debug, Clone, Copy)]
@@ -259,6 +301,105 @@ pub struct NvmeIdNs {
     pub vs: [u8; 3712],
 }
 
+// Per-namespace geometry cached off the active LBA format (lbaf[flbas & 0xf])
+// the last time identify_namespace ran, plus the PI type read from dps.
+// Lets read_data/write_data size transfers and program protection fields
+// without re-identifying the namespace on every I/O.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct NsGeometry {
+    pub nsid: u32,
+    pub lba_size: u32,
+    pub ms_size: u16,
+    pub pi_type: u8,
+}
+
+// Namespaces tracked by the geometry cache at once; plenty for the handful
+// of namespaces a single controller typically exposes.
+const NVME_NS_GEOMETRY_SLOTS: usize = 8;
+
+// End-to-end protection parameters for a single read/write, mirroring the
+// PRACT/PRCHK bits in cdw12 and the reference/application tag fields in
+// cdw14/cdw15 used by PI types 1-3.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PiParams {
+    pub pract: bool,
+    pub prchk_guard: bool,
+    pub prchk_apptag: bool,
+    pub prchk_reftag: bool,
+    pub ref_tag: u32,
+    pub app_tag: u16,
+    pub app_tag_mask: u16,
+}
+
+// cdw12 PRACT/PRCHK bit positions (NVMe base spec Read/Write command).
+const NVME_RW_PRCHK_REFTAG: u32 = 1 << 26;
+const NVME_RW_PRCHK_APPTAG: u32 = 1 << 27;
+const NVME_RW_PRCHK_GUARD: u32 = 1 << 28;
+const NVME_RW_PRACT: u32 = 1 << 29;
+
+// Dataset Management range descriptor: one 16-byte entry per LBA range
+// passed to the DSM command, each naming its own deallocate extent.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy)]
+struct NvmeDsmRange {
+    context_attrs: u32,
+    length: u32,
+    slba: u64,
+}
+
+// NVMe SMART / Health Information log page (log id 0x02, simplified)
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy)]
+pub struct NvmeSmartLog {
+    pub critical_warning: u8,
+    pub temperature: [u8; 2],
+    pub avail_spare: u8,
+    pub spare_thresh: u8,
+    pub percent_used: u8,
+    pub endurance_crit_warning: u8,
+    pub rsvd7: [u8; 25],
+    pub data_units_read: [u8; 16],
+    pub data_units_written: [u8; 16],
+    pub host_read_commands: [u8; 16],
+    pub host_write_commands: [u8; 16],
+    pub ctrl_busy_time: [u8; 16],
+    pub power_cycles: [u8; 16],
+    pub power_on_hours: [u8; 16],
+    pub unsafe_shutdowns: [u8; 16],
+    pub media_errors: [u8; 16],
+    pub num_err_log_entries: [u8; 16],
+    pub warning_temp_time: u32,
+    pub critical_comp_time: u32,
+    pub temp_sensor: [u16; 8],
+    pub rsvd216: [u8; 296],
+}
+
+// Number of error-log ring entries get_log_page(NVME_LOG_ERROR) pulls in one
+// go; the spec lets this vary per controller (ELPE+1) but 16 covers the
+// common case and keeps the transfer a single page-ish read.
+const NVME_ERROR_LOG_ENTRIES: usize = 16;
+
+// NVMe error information log entry (log id 0x01, 64 bytes)
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy)]
+pub struct NvmeErrorLogEntry {
+    pub error_count: u64,
+    pub sqid: u16,
+    pub cmdid: u16,
+    pub status_field: u16,
+    pub param_error_location: u16,
+    pub lba: u64,
+    pub nsid: u32,
+    pub vs: u8,
+    pub trtype: u8,
+    pub rsvd30: [u8; 2],
+    pub cs: u64,
+    pub trtype_spec_info: u16,
+    pub rsvd42: [u8; 22],
+}
+
 // NVMe queue structure
 #[repr(C)]
 pub struct NvmeQueue {
@@ -267,17 +408,32 @@ pub struct NvmeQueue {
     pub sq_dma_addr: u64,
     pub cq_dma_addr: u64,
     pub sq_tail: u16,
+    pub sq_head: u16,
     pub cq_head: u16,
     pub cq_phase: u8,
     pub q_depth: u16,
     pub qid: u16,
     pub cq_vector: u8,
+    pub cid_counter: u16,
+}
+
+// Abstraction over the platform's DMA allocator so this driver never
+// stuffs a raw CPU pointer into a PRP field. `alloc_coherent`/`free_coherent`
+// hand out DMA-coherent memory for queue rings and PRP list pages (virtual
+// pointer plus the bus address the device should see); `map`/`unmap` resolve
+// a caller-supplied buffer to a bus address without copying it.
+pub trait DmaProvider {
+    fn alloc_coherent(&mut self, size: usize) -> Option<(*mut u8, u64)>;
+    fn free_coherent(&mut self, virt: *mut u8, bus_addr: u64, size: usize);
+    fn map(&mut self, virt: *const u8, size: usize) -> Option<u64>;
+    fn unmap(&mut self, bus_addr: u64, size: usize);
 }
 
 // NVMe controller structure
 #[repr(C)]
 pub struct NvmeCtrl {
     pub bar: *mut u8,
+    pub dma: Box<dyn DmaProvider>,
     pub admin_q: NvmeQueue,
     pub queues: [Option<NvmeQueue>; 64],
     pub queue_count: u16,
@@ -291,12 +447,29 @@ pub struct NvmeCtrl {
     pub max_transfer_shift: u8,
     pub shutdown_timeout: u16,
     pub kato: u16,
+    pub next_io_queue: u16,
+    aen_cids: [Option<u16>; NVME_AEN_POOL_SIZE],
+    aen_callback: Option<extern "C" fn(event_type: u8, log_page: u8)>,
+    // (event_type, log_page) pairs decoded off the CQ but not yet resolved:
+    // resolving one means a log-page read, which blocks on wait_completion,
+    // and process_cq is reentered by wait_completion's own poll loop, so
+    // that can't happen from inside the reap path itself.
+    pending_aens: [Option<(u8, u8)>; NVME_AEN_PENDING_SIZE],
+    // Poll-iteration budgets, mirroring Linux's io_timeout/admin_timeout
+    // module params but expressed in process_cq polls rather than jiffies
+    // since this driver has no wall clock of its own.
+    pub admin_timeout: u32,
+    pub io_timeout: u32,
+    pub max_retries: u8,
+    oncs: u16,
+    ns_geometry: [Option<NsGeometry>; NVME_NS_GEOMETRY_SLOTS],
 }
 
 impl NvmeCtrl {
-    pub fn new(bar: *mut u8) -> Self {
+    pub fn new(bar: *mut u8, dma: Box<dyn DmaProvider>) -> Self {
         Self {
             bar,
+            dma,
             admin_q: unsafe { mem::zeroed() },
             queues: [None; 64],
             queue_count: 0,
@@ -310,6 +483,15 @@ impl NvmeCtrl {
             max_transfer_shift: 0,
             shutdown_timeout: 0,
             kato: 0,
+            next_io_queue: 1,
+            aen_cids: [None; NVME_AEN_POOL_SIZE],
+            aen_callback: None,
+            pending_aens: [None; NVME_AEN_PENDING_SIZE],
+            admin_timeout: NVME_CQ_POLL_ITERS,
+            io_timeout: NVME_CQ_POLL_ITERS,
+            max_retries: NVME_DEFAULT_MAX_RETRIES,
+            oncs: 0,
+            ns_geometry: [None; NVME_NS_GEOMETRY_SLOTS],
         }
     }
 
@@ -384,26 +566,191 @@ impl NvmeCtrl {
         if timeout == 0 {
             return Err(-2);
         }
-        
+
+        self.setup_keep_alive()?;
+        self.setup_aen()?;
+
         Ok(())
     }
-    
+
+    // Identify the controller to read `kas` (Keep Alive Support, in 100ms
+    // units) and, if the controller wants one, program a keep-alive timeout
+    // via Set Features so it doesn't time the host out between commands.
+    fn setup_keep_alive(&mut self) -> Result<(), i32> {
+        let size = mem::size_of::<NvmeIdCtrl>();
+        let (virt, bus) = self.dma.alloc_coherent(size).ok_or(-12)?;
+
+        let result = self.identify_controller(virt as *mut NvmeIdCtrl);
+        let kas = if result.is_ok() {
+            unsafe { (*(virt as *const NvmeIdCtrl)).kas }
+        } else {
+            0
+        };
+
+        self.dma.free_coherent(virt, bus, size);
+        result?;
+
+        if kas == 0 {
+            self.kato = 0;
+            return Ok(());
+        }
+
+        // Default to NVME_DEFAULT_KATO_MS, but never below one KAS unit.
+        let kato_ms = (kas as u32 * 100).max(NVME_DEFAULT_KATO_MS);
+
+        let mut cmd: NvmeCommand = unsafe { mem::zeroed() };
+        cmd.cdw0 = NVME_ADMIN_SET_FEATURES as u32;
+        cmd.cdw10 = NVME_FEAT_KEEP_ALIVE_TIMER as u32;
+        cmd.cdw11 = kato_ms;
+
+        self.submit_and_wait(&cmd)?;
+        // kato_ms can exceed u16::MAX (KAS goes up to 6553.5s); saturate
+        // rather than truncate so `self.kato == 0` can't happen here while
+        // the controller still expects keep-alives.
+        self.kato = kato_ms.min(u16::MAX as u32) as u16;
+
+        Ok(())
+    }
+
+    // Submit a Keep Alive (opcode 0x18) to reset the controller's KATO
+    // countdown. The embedding OS should call this roughly every kato/2.
+    pub fn keep_alive(&mut self) -> Result<(), i32> {
+        if self.kato == 0 {
+            return Ok(());
+        }
+
+        let mut cmd: NvmeCommand = unsafe { mem::zeroed() };
+        cmd.cdw0 = NVME_ADMIN_KEEP_ALIVE as u32;
+        let cid = self.submit_admin_cmd(&cmd)?;
+        self.wait_completion(0, cid)
+    }
+
+    // Keep `aerl + 1` Async Event Request commands outstanding (capped by
+    // NVME_AEN_POOL_SIZE) so the controller always has somewhere to report
+    // notice, SMART and error events instead of the host having to poll.
+    fn setup_aen(&mut self) -> Result<(), i32> {
+        let size = mem::size_of::<NvmeIdCtrl>();
+        let (virt, bus) = self.dma.alloc_coherent(size).ok_or(-12)?;
+
+        let result = self.identify_controller(virt as *mut NvmeIdCtrl);
+        let aerl = if result.is_ok() {
+            unsafe { (*(virt as *const NvmeIdCtrl)).aerl }
+        } else {
+            0
+        };
+
+        self.dma.free_coherent(virt, bus, size);
+        result?;
+
+        let count = ((aerl as usize) + 1).min(NVME_AEN_POOL_SIZE);
+        for _ in 0..count {
+            self.post_aen();
+        }
+
+        Ok(())
+    }
+
+    // Submit a single Async Event Request and track its command id in the
+    // AEN pool so process_cq can tell its completion apart from a normal
+    // command's.
+    fn post_aen(&mut self) {
+        let slot = match self.aen_cids.iter().position(|c| c.is_none()) {
+            Some(i) => i,
+            None => return,
+        };
+
+        let mut cmd: NvmeCommand = unsafe { mem::zeroed() };
+        cmd.cdw0 = NVME_ADMIN_ASYNC_EVENT as u32;
+
+        if let Ok(cid) = self.submit_admin_cmd(&cmd) {
+            self.aen_cids[slot] = Some(cid);
+        }
+    }
+
+    fn take_aen_cid(&mut self, cid: u16) -> bool {
+        for slot in self.aen_cids.iter_mut() {
+            if *slot == Some(cid) {
+                *slot = None;
+                return true;
+            }
+        }
+        false
+    }
+
+    // Decode the AEN completion's result dword into event type (bits 2:0)
+    // and log page identifier (bits 23:16), stash the pair for
+    // `dispatch_pending_aens` to resolve, and top the pool back up.
+    //
+    // This must NOT resolve the AEN (log-page read + callback) here: that
+    // needs wait_completion, and we're already inside process_cq's reap
+    // path, possibly nested under some other caller's wait_completion poll
+    // loop. Reentering wait_completion from here would drain completions
+    // looking for the log-page read's cid, and if the outer call's cid
+    // shows up during that nested drain it gets silently dropped (the
+    // `continue` branch in wait_completion doesn't know to hand it back),
+    // leaving the outer call to spin until it times out and aborts an
+    // already-completed command.
+    fn handle_aen_completion(&mut self, entry: NvmeCompletion) {
+        let result = entry.result;
+        let event_type = (result & 0x7) as u8;
+        let mut log_page = ((result >> 16) & 0xff) as u8;
+
+        if event_type == NVME_AEN_TYPE_NOTICE && log_page == 0 {
+            log_page = NVME_LOG_CHANGED_NS;
+        }
+
+        if let Some(slot) = self.pending_aens.iter_mut().find(|s| s.is_none()) {
+            *slot = Some((event_type, log_page));
+        }
+
+        self.post_aen();
+    }
+
+    // Resolve any AENs `handle_aen_completion` stashed: read the log page
+    // each points at and hand it to the registered callback. Call this from
+    // a separate, non-reentrant poll (e.g. a bottom-half or periodic tick)
+    // that is never itself running inside a wait_completion loop.
+    pub fn dispatch_pending_aens(&mut self) {
+        for slot in 0..self.pending_aens.len() {
+            let (event_type, log_page) = match self.pending_aens[slot].take() {
+                Some(pair) => pair,
+                None => continue,
+            };
+
+            let mut scratch = [0u8; NVME_AEN_LOG_SCRATCH];
+            let _ = self.get_log_page(0xffff_ffff, log_page, scratch.as_mut_ptr(), scratch.len());
+
+            if let Some(cb) = self.aen_callback {
+                cb(event_type, log_page);
+            }
+        }
+    }
+
+    // Register the callback the embedding OS wants invoked whenever an AEN
+    // lands (event type, log page id). Pass `None` to disable notifications.
+    pub fn set_aen_callback(&mut self, cb: Option<extern "C" fn(u8, u8)>) {
+        self.aen_callback = cb;
+    }
+
     fn setup_admin_queue(&mut self) -> Result<(), i32> {
-        // Allocate admin queue memory (simplified - would use proper DMA allocation)
         let sq_size = 64 * mem::size_of::<NvmeCommand>();
         let cq_size = 64 * mem::size_of::<NvmeCompletion>();
-        
-        // In real implementation, these would be DMA-coherent allocations
-        self.admin_q.sq_cmds = ptr::null_mut(); // Would allocate DMA memory
-        self.admin_q.cq_cmds = ptr::null_mut(); // Would allocate DMA memory
-        self.admin_q.sq_dma_addr = 0; // Would be real DMA address
-        self.admin_q.cq_dma_addr = 0; // Would be real DMA address
-        
+
+        let (sq_virt, sq_bus) = self.dma.alloc_coherent(sq_size).ok_or(-12)?;
+        let (cq_virt, cq_bus) = self.dma.alloc_coherent(cq_size).ok_or(-12)?;
+
+        self.admin_q.sq_cmds = sq_virt as *mut NvmeCommand;
+        self.admin_q.cq_cmds = cq_virt as *mut NvmeCompletion;
+        self.admin_q.sq_dma_addr = sq_bus;
+        self.admin_q.cq_dma_addr = cq_bus;
+
         self.admin_q.q_depth = 64;
         self.admin_q.qid = 0;
         self.admin_q.sq_tail = 0;
+        self.admin_q.sq_head = 0;
         self.admin_q.cq_head = 0;
         self.admin_q.cq_phase = 1;
+        self.admin_q.cid_counter = 0;
         
         // Set admin queue attributes
         let aqa = ((64 - 1) << 16) | (64 - 1);
@@ -420,123 +767,792 @@ impl NvmeCtrl {
         if self.admin_q.sq_cmds.is_null() {
             return Err(-1);
         }
-        
+
+        // Tag the command with a per-queue command id (cdw0[31:16]) so its
+        // completion can be matched up later by process_cq/wait_completion.
+        let cid = self.admin_q.cid_counter;
+        self.admin_q.cid_counter = self.admin_q.cid_counter.wrapping_add(1);
+
+        let mut cmd = *cmd;
+        cmd.cdw0 = (cmd.cdw0 & 0x0000_ffff) | ((cid as u32) << 16);
+
         let tail = self.admin_q.sq_tail;
-        
+
         unsafe {
-            ptr::write_volatile(self.admin_q.sq_cmds.add(tail as usize), *cmd);
+            ptr::write_volatile(self.admin_q.sq_cmds.add(tail as usize), cmd);
         }
-        
+
         self.admin_q.sq_tail = (tail + 1) % self.admin_q.q_depth;
-        
+
         // Ring doorbell
         let doorbell_offset = 0x1000 + (0 * 2 * self.db_stride);
         self.write_reg32(doorbell_offset as u64, self.admin_q.sq_tail as u32);
-        
-        Ok(tail)
+
+        Ok(cid)
     }
-    
+
+    // Reap a single completion from queue `qid` (0 is the admin queue).
+    // Returns None when the phase bit of the next CQ slot doesn't match the
+    // queue's current phase, i.e. no new completion has landed yet.
+    pub fn process_cq(&mut self, qid: u16) -> Option<NvmeCompletion> {
+        let bar = self.bar;
+        let db_stride = self.db_stride;
+        let q = self.queue_mut(qid)?;
+        let entry = Self::reap_cq_entry(bar, db_stride, q)?;
+
+        // AENs never complete in the normal request/response flow, so they
+        // must be pulled out here rather than handed back to whatever
+        // caller happens to be draining the admin CQ (wait_completion would
+        // otherwise just shrug them off as "some other in-flight command").
+        if qid == 0 && self.take_aen_cid(entry.command_id) {
+            self.handle_aen_completion(entry);
+            return None;
+        }
+
+        Some(entry)
+    }
+
+    fn queue_mut(&mut self, qid: u16) -> Option<&mut NvmeQueue> {
+        if qid == 0 {
+            Some(&mut self.admin_q)
+        } else {
+            self.queues.get_mut(qid as usize)?.as_mut()
+        }
+    }
+
+    fn reap_cq_entry(bar: *mut u8, db_stride: u32, q: &mut NvmeQueue) -> Option<NvmeCompletion> {
+        if q.cq_cmds.is_null() {
+            return None;
+        }
+
+        let entry = unsafe { ptr::read_volatile(q.cq_cmds.add(q.cq_head as usize)) };
+        if (entry.status & 1) as u8 != q.cq_phase {
+            return None;
+        }
+
+        // The device reports the SQ head it has consumed up to, freeing
+        // those slots for reuse.
+        q.sq_head = entry.sq_head;
+
+        q.cq_head += 1;
+        if q.cq_head >= q.q_depth {
+            q.cq_head = 0;
+            q.cq_phase ^= 1;
+        }
+
+        let db_offset = 0x1000 + (2 * q.qid as u64 + 1) * db_stride as u64;
+        unsafe {
+            ptr::write_volatile((bar.add(db_offset as usize)) as *mut u32, q.cq_head as u32);
+        }
+
+        Some(entry)
+    }
+
+    // Block until the completion for `cid` shows up on queue `qid` (0 is
+    // the admin queue), mapping its status word into a driver Result. Uses
+    // admin_timeout/io_timeout as the poll-iteration deadline, aborts the
+    // command if it's blown through that deadline, and fails fast if the
+    // controller reports CSTS.CFS (nothing still in flight is coming back).
+    pub fn wait_completion(&mut self, qid: u16, cid: u16) -> Result<(), i32> {
+        let mut timeout = if qid == 0 { self.admin_timeout } else { self.io_timeout };
+
+        loop {
+            if (self.read_reg32(NVME_REG_CSTS) & NVME_CSTS_CFS) != 0 {
+                return Err(-5); // EIO-ish: controller fatal status
+            }
+
+            if let Some(entry) = self.process_cq(qid) {
+                if entry.command_id == cid {
+                    return decode_status(entry.status);
+                }
+                // Completion for some other in-flight command; keep
+                // draining, but still fall through to the timeout
+                // decrement below -- otherwise a command the controller
+                // silently drops never times out as long as other
+                // commands on the same queue keep completing.
+            }
+
+            timeout -= 1;
+            if timeout == 0 {
+                self.abort_cmd(qid, cid);
+                return Err(-62); // ETIME-ish: completion never arrived
+            }
+        }
+    }
+
+    // Ask the controller to abort `cid` on SQ `sqid` (opcode 0x08, cdw10 is
+    // SQID[15:0] | CID[31:16]). Best-effort: the original command's timeout
+    // is what the caller reports, not whether the abort itself lands.
+    fn abort_cmd(&mut self, sqid: u16, cid: u16) {
+        let mut cmd: NvmeCommand = unsafe { mem::zeroed() };
+        cmd.cdw0 = NVME_ADMIN_ABORT_CMD as u32;
+        cmd.cdw10 = (sqid as u32) | ((cid as u32) << 16);
+
+        if let Ok(abort_cid) = self.submit_admin_cmd(&cmd) {
+            let _ = self.wait_completion(0, abort_cid);
+        }
+    }
+
     pub fn identify_controller(&mut self, data: *mut NvmeIdCtrl) -> Result<(), i32> {
+        let bus_addr = self.dma.map(data as *const u8, mem::size_of::<NvmeIdCtrl>()).ok_or(-5)?;
+
         let mut cmd: NvmeCommand = unsafe { mem::zeroed() };
-        
         cmd.cdw0 = NVME_ADMIN_IDENTIFY as u32;
         cmd.nsid = 0;
-        cmd.prp1 = data as u64; // In real implementation, would be DMA address
+        cmd.prp1 = bus_addr;
         cmd.cdw10 = 1; // Controller identify
-        
-        self.submit_admin_cmd(&cmd)?;
-        
-        // Wait for completion (simplified)
-        // In real implementation, would wait for interrupt or poll completion queue
-        
+
+        let cid = self.submit_admin_cmd(&cmd)?;
+        let result = self.wait_completion(0, cid);
+        self.dma.unmap(bus_addr, mem::size_of::<NvmeIdCtrl>());
+        result?;
+
+        // MDTS is expressed in units of 2^mdts * page_size; cache it so
+        // read_data/write_data can reject or split oversized transfers.
+        // Cache ONCS too so discard()/write_zeroes() can refuse to send a
+        // command the namespace never advertised support for.
+        unsafe {
+            self.max_transfer_shift = (*data).mdts;
+            self.oncs = (*data).oncs;
+        }
+
         Ok(())
     }
-    
+
     pub fn identify_namespace(&mut self, nsid: u32, data: *mut NvmeIdNs) -> Result<(), i32> {
+        let bus_addr = self.dma.map(data as *const u8, mem::size_of::<NvmeIdNs>()).ok_or(-5)?;
+
         let mut cmd: NvmeCommand = unsafe { mem::zeroed() };
-        
         cmd.cdw0 = NVME_ADMIN_IDENTIFY as u32;
         cmd.nsid = nsid;
-        cmd.prp1 = data as u64; // In real implementation, would be DMA address
+        cmd.prp1 = bus_addr;
         cmd.cdw10 = 0; // Namespace identify
-        
-        self.submit_admin_cmd(&cmd)?;
-        
+
+        let cid = self.submit_admin_cmd(&cmd)?;
+        let result = self.wait_completion(0, cid);
+        self.dma.unmap(bus_addr, mem::size_of::<NvmeIdNs>());
+        result?;
+
+        // lbaf[flbas & 0xf] dword: RP in bits 31:24, LBADS (log2 bytes) in
+        // bits 23:16, MS (metadata size) in bits 15:0.
+        unsafe {
+            let flbas = (*data).flbas & 0xf;
+            let lbaf = (*data).lbaf[flbas as usize];
+            let lbads = ((lbaf >> 16) & 0xff) as u8;
+            let ms_size = (lbaf & 0xffff) as u16;
+            let pi_type = (*data).dps & 0x7;
+            self.cache_ns_geometry(nsid, lbads, ms_size, pi_type);
+        }
+
         Ok(())
     }
-    
+
+    fn cache_ns_geometry(&mut self, nsid: u32, lbads: u8, ms_size: u16, pi_type: u8) {
+        let geom = NsGeometry {
+            nsid,
+            lba_size: 1u32 << lbads,
+            ms_size,
+            pi_type,
+        };
+
+        if let Some(slot) = self.ns_geometry.iter_mut().find(|s| matches!(s, Some(g) if g.nsid == nsid)) {
+            *slot = Some(geom);
+            return;
+        }
+        if let Some(slot) = self.ns_geometry.iter_mut().find(|s| s.is_none()) {
+            *slot = Some(geom);
+            return;
+        }
+
+        // Cache is full; evicting slot 0 is simpler than proper LRU and
+        // good enough for the handful of namespaces in play.
+        self.ns_geometry[0] = Some(geom);
+    }
+
+    pub fn ns_geometry(&self, nsid: u32) -> Option<NsGeometry> {
+        self.ns_geometry.iter().flatten().find(|g| g.nsid == nsid).copied()
+    }
+
+    // Metadata buffer size callers should allocate per block of `nsid`,
+    // mirroring Linux's max_integrity_segments sizing hint.
+    pub fn metadata_size(&self, nsid: u32) -> u16 {
+        self.ns_geometry(nsid).map_or(0, |g| g.ms_size)
+    }
+
+    // Read `len` bytes of log page `log_id` into `buffer`. The transfer size
+    // goes in as a zero-based dword count (NUMD), split low/high across
+    // cdw10[31:16]/cdw11 per the spec; cdw12/cdw13 carry the (unused here)
+    // 64-bit log page offset for controllers that support partial reads.
+    pub fn get_log_page(&mut self, nsid: u32, log_id: u8, buffer: *mut u8, len: usize) -> Result<(), i32> {
+        let bus_addr = self.dma.map(buffer as *const u8, len).ok_or(-5)?;
+
+        let numd = (len as u32 / 4).saturating_sub(1);
+
+        let mut cmd: NvmeCommand = unsafe { mem::zeroed() };
+        cmd.cdw0 = NVME_ADMIN_GET_LOG_PAGE as u32;
+        cmd.nsid = nsid;
+        cmd.prp1 = bus_addr;
+        cmd.cdw10 = (log_id as u32) | ((numd & 0xffff) << 16);
+        cmd.cdw11 = numd >> 16;
+        cmd.cdw12 = 0;
+        cmd.cdw13 = 0;
+
+        let cid = self.submit_admin_cmd(&cmd)?;
+        let result = self.wait_completion(0, cid);
+        self.dma.unmap(bus_addr, len);
+        result
+    }
+
+    pub fn smart_log(&mut self) -> Result<NvmeSmartLog, i32> {
+        let mut log: NvmeSmartLog = unsafe { mem::zeroed() };
+        let len = mem::size_of::<NvmeSmartLog>();
+        self.get_log_page(0xffff_ffff, NVME_LOG_SMART, &mut log as *mut _ as *mut u8, len)?;
+        Ok(log)
+    }
+
+    pub fn error_log(&mut self) -> Result<[NvmeErrorLogEntry; NVME_ERROR_LOG_ENTRIES], i32> {
+        let mut log = [unsafe { mem::zeroed::<NvmeErrorLogEntry>() }; NVME_ERROR_LOG_ENTRIES];
+        let len = mem::size_of_val(&log);
+        self.get_log_page(0xffff_ffff, NVME_LOG_ERROR, log.as_mut_ptr() as *mut u8, len)?;
+        Ok(log)
+    }
+
+    // Allocate real CQ/SQ rings through the DmaProvider and wire them up
+    // with the controller via Create CQ / Create SQ, mirroring the Linux
+    // split of "allocate the queue" from "tell the hardware about it".
     pub fn create_io_queue(&mut self, qid: u16, qsize: u16) -> Result<(), i32> {
-        if qid == 0 || qid >= 64 {
+        if qid == 0 || qid as usize >= self.queues.len() {
             return Err(-1);
         }
-        
+        if self.queues[qid as usize].is_some() {
+            return Err(-17); // EEXIST-ish: queue already created
+        }
+
+        let sq_size = qsize as usize * mem::size_of::<NvmeCommand>();
+        let cq_size = qsize as usize * mem::size_of::<NvmeCompletion>();
+
+        let (sq_virt, sq_bus) = self.dma.alloc_coherent(sq_size).ok_or(-12)?;
+        let (cq_virt, cq_bus) = self.dma.alloc_coherent(cq_size).ok_or(-12)?;
+
+        // One MSI-X vector per I/O queue; vector 0 is reserved for the
+        // admin queue.
+        let cq_vector = qid;
+
         // Create completion queue first
         let mut cmd: NvmeCommand = unsafe { mem::zeroed() };
         cmd.cdw0 = NVME_ADMIN_CREATE_CQ as u32;
-        cmd.cdw10 = ((qsize - 1) << 16) | qid as u32;
-        cmd.cdw11 = 1; // Physically contiguous
-        
-        self.submit_admin_cmd(&cmd)?;
-        
+        cmd.prp1 = cq_bus;
+        cmd.cdw10 = ((qsize as u32 - 1) << 16) | qid as u32;
+        cmd.cdw11 = ((cq_vector as u32) << 16) | (1 << 1) | 1; // vector, IEN, PC
+
+        let cid = self.submit_admin_cmd(&cmd)?;
+        self.wait_completion(0, cid)?;
+
         // Create submission queue
-        cmd = unsafe { mem::zeroed() };
+        let mut cmd: NvmeCommand = unsafe { mem::zeroed() };
         cmd.cdw0 = NVME_ADMIN_CREATE_SQ as u32;
-        cmd.cdw10 = ((qsize - 1) << 16) | qid as u32;
-        cmd.cdw11 = (qid << 16) | 1; // Associated CQ ID and physically contiguous
-        
-        self.submit_admin_cmd(&cmd)?;
-        
+        cmd.prp1 = sq_bus;
+        cmd.cdw10 = ((qsize as u32 - 1) << 16) | qid as u32;
+        cmd.cdw11 = ((qid as u32) << 16) | 1; // associated CQ id, PC
+
+        let cid = self.submit_admin_cmd(&cmd)?;
+        self.wait_completion(0, cid)?;
+
+        self.queues[qid as usize] = Some(NvmeQueue {
+            sq_cmds: sq_virt as *mut NvmeCommand,
+            cq_cmds: cq_virt as *mut NvmeCompletion,
+            sq_dma_addr: sq_bus,
+            cq_dma_addr: cq_bus,
+            sq_tail: 0,
+            sq_head: 0,
+            cq_head: 0,
+            cq_phase: 1,
+            q_depth: qsize,
+            qid,
+            cq_vector: cq_vector as u8,
+            cid_counter: 0,
+        });
+
+        self.queue_count += 1;
+        self.online_queues += 1;
+        if qid > self.max_qid {
+            self.max_qid = qid;
+        }
+
         Ok(())
     }
-    
-    pub fn read_data(&mut self, nsid: u32, lba: u64, blocks: u16, buffer: *mut u8) -> Result<(), i32> {
+
+    // Tear down an I/O queue: Delete SQ before Delete CQ (the order the
+    // spec requires), then release its DMA-coherent rings.
+    pub fn delete_io_queue(&mut self, qid: u16) -> Result<(), i32> {
+        if qid == 0 || qid as usize >= self.queues.len() || self.queues[qid as usize].is_none() {
+            return Err(-1);
+        }
+
+        let mut cmd: NvmeCommand = unsafe { mem::zeroed() };
+        cmd.cdw0 = NVME_ADMIN_DELETE_SQ as u32;
+        cmd.cdw10 = qid as u32;
+        let cid = self.submit_admin_cmd(&cmd)?;
+        self.wait_completion(0, cid)?;
+
+        let mut cmd: NvmeCommand = unsafe { mem::zeroed() };
+        cmd.cdw0 = NVME_ADMIN_DELETE_CQ as u32;
+        cmd.cdw10 = qid as u32;
+        let cid = self.submit_admin_cmd(&cmd)?;
+        self.wait_completion(0, cid)?;
+
+        if let Some(q) = self.queues[qid as usize].take() {
+            let sq_size = q.q_depth as usize * mem::size_of::<NvmeCommand>();
+            let cq_size = q.q_depth as usize * mem::size_of::<NvmeCompletion>();
+            self.dma.free_coherent(q.sq_cmds as *mut u8, q.sq_dma_addr, sq_size);
+            self.dma.free_coherent(q.cq_cmds as *mut u8, q.cq_dma_addr, cq_size);
+        }
+
+        self.queue_count -= 1;
+        self.online_queues = self.online_queues.saturating_sub(1);
+
+        Ok(())
+    }
+
+    // Submit `cmd` to I/O queue `qid`'s SQ ring and ring its doorbell.
+    pub fn submit_io_cmd(&mut self, qid: u16, cmd: &NvmeCommand) -> Result<u16, i32> {
+        if qid == 0 || qid as usize >= self.queues.len() {
+            return Err(-1);
+        }
+
+        let bar = self.bar;
+        let db_stride = self.db_stride;
+
+        let q = self.queues[qid as usize].as_mut().ok_or(-1)?;
+        if q.sq_cmds.is_null() {
+            return Err(-1);
+        }
+
+        let cid = q.cid_counter;
+        q.cid_counter = q.cid_counter.wrapping_add(1);
+
+        let mut cmd = *cmd;
+        cmd.cdw0 = (cmd.cdw0 & 0x0000_ffff) | ((cid as u32) << 16);
+
+        let tail = q.sq_tail;
+        unsafe {
+            ptr::write_volatile(q.sq_cmds.add(tail as usize), cmd);
+        }
+        q.sq_tail = (tail + 1) % q.q_depth;
+
+        let doorbell_offset = 0x1000 + (2 * qid as u64) * db_stride as u64;
+        unsafe {
+            ptr::write_volatile((bar.add(doorbell_offset as usize)) as *mut u32, q.sq_tail as u32);
+        }
+
+        Ok(cid)
+    }
+
+    // Round-robin across the online I/O queues so read/write/flush spread
+    // load instead of all landing on queue 1. Returns None until at least
+    // one I/O queue has been created.
+    fn pick_io_queue(&mut self) -> Option<u16> {
+        if self.online_queues == 0 {
+            return None;
+        }
+
+        for _ in 0..self.max_qid {
+            let qid = self.next_io_queue;
+            self.next_io_queue = if qid >= self.max_qid { 1 } else { qid + 1 };
+            if self.queues[qid as usize].is_some() {
+                return Some(qid);
+            }
+        }
+
+        None
+    }
+
+    // `meta`/`pi` are optional (pass a null pointer and None respectively
+    // for namespaces without end-to-end protection); when set, `meta` must
+    // point at `blocks * metadata_size(nsid)` bytes.
+    pub fn read_data(&mut self, nsid: u32, lba: u64, blocks: u16, buffer: *mut u8, meta: *mut u8, pi: Option<PiParams>) -> Result<(), i32> {
+        let geom = self.ns_geometry(nsid);
+        let lba_size = geom.map_or(DEFAULT_LBA_SIZE, |g| g.lba_size as u64);
+        let len = lba_size * blocks as u64;
+        if len > self.max_transfer_bytes() {
+            return Err(-7); // exceeds MDTS
+        }
+
+        let prp = self.build_prp(buffer as *const u8, len as usize)?;
+
         let mut cmd: NvmeCommand = unsafe { mem::zeroed() };
-        
         cmd.cdw0 = NVME_CMD_READ as u32;
         cmd.nsid = nsid;
-        cmd.prp1 = buffer as u64; // In real implementation, would be DMA address
+        cmd.prp1 = prp.prp1;
+        cmd.prp2 = prp.prp2;
         cmd.cdw10 = (lba & 0xffffffff) as u32;
         cmd.cdw11 = (lba >> 32) as u32;
         cmd.cdw12 = (blocks - 1) as u32; // 0-based
-        
-        // Submit to I/O queue (simplified - using admin queue for now)
-        self.submit_admin_cmd(&cmd)?;
-        
-        Ok(())
+
+        let meta_len = geom.map_or(0, |g| g.ms_size as usize) * blocks as usize;
+        let meta_bus = if !meta.is_null() && meta_len > 0 {
+            self.dma.map(meta as *const u8, meta_len)
+        } else {
+            None
+        };
+        if let Some(bus) = meta_bus {
+            cmd.metadata = bus;
+        }
+        self.apply_pi_params(&mut cmd, pi);
+
+        let result = self.submit_and_wait(&cmd);
+        if let Some(bus) = meta_bus {
+            self.dma.unmap(bus, meta_len);
+        }
+        self.release_prp(prp);
+        result
     }
-    
-    pub fn write_data(&mut self, nsid: u32, lba: u64, blocks: u16, buffer: *const u8) -> Result<(), i32> {
+
+    pub fn write_data(&mut self, nsid: u32, lba: u64, blocks: u16, buffer: *const u8, meta: *const u8, pi: Option<PiParams>) -> Result<(), i32> {
+        let geom = self.ns_geometry(nsid);
+        let lba_size = geom.map_or(DEFAULT_LBA_SIZE, |g| g.lba_size as u64);
+        let len = lba_size * blocks as u64;
+        if len > self.max_transfer_bytes() {
+            return Err(-7); // exceeds MDTS
+        }
+
+        let prp = self.build_prp(buffer, len as usize)?;
+
         let mut cmd: NvmeCommand = unsafe { mem::zeroed() };
-        
         cmd.cdw0 = NVME_CMD_WRITE as u32;
         cmd.nsid = nsid;
-        cmd.prp1 = buffer as u64; // In real implementation, would be DMA address
+        cmd.prp1 = prp.prp1;
+        cmd.prp2 = prp.prp2;
         cmd.cdw10 = (lba & 0xffffffff) as u32;
         cmd.cdw11 = (lba >> 32) as u32;
         cmd.cdw12 = (blocks - 1) as u32; // 0-based
-        
-        // Submit to I/O queue (simplified - using admin queue for now)
-        self.submit_admin_cmd(&cmd)?;
-        
-        Ok(())
+
+        let meta_len = geom.map_or(0, |g| g.ms_size as usize) * blocks as usize;
+        let meta_bus = if !meta.is_null() && meta_len > 0 {
+            self.dma.map(meta, meta_len)
+        } else {
+            None
+        };
+        if let Some(bus) = meta_bus {
+            cmd.metadata = bus;
+        }
+        self.apply_pi_params(&mut cmd, pi);
+
+        let result = self.submit_and_wait(&cmd);
+        if let Some(bus) = meta_bus {
+            self.dma.unmap(bus, meta_len);
+        }
+        self.release_prp(prp);
+        result
     }
-    
+
+    // Fold PRACT/PRCHK into cdw12 and the reference/application tag fields
+    // into cdw14/cdw15, as used by PI types 1-3.
+    fn apply_pi_params(&self, cmd: &mut NvmeCommand, pi: Option<PiParams>) {
+        let pi = match pi {
+            Some(pi) => pi,
+            None => return,
+        };
+
+        if pi.pract {
+            cmd.cdw12 |= NVME_RW_PRACT;
+        }
+        if pi.prchk_reftag {
+            cmd.cdw12 |= NVME_RW_PRCHK_REFTAG;
+        }
+        if pi.prchk_apptag {
+            cmd.cdw12 |= NVME_RW_PRCHK_APPTAG;
+        }
+        if pi.prchk_guard {
+            cmd.cdw12 |= NVME_RW_PRCHK_GUARD;
+        }
+
+        cmd.cdw14 = pi.ref_tag;
+        cmd.cdw15 = (pi.app_tag as u32) | ((pi.app_tag_mask as u32) << 16);
+    }
+
     pub fn flush(&mut self, nsid: u32) -> Result<(), i32> {
         let mut cmd: NvmeCommand = unsafe { mem::zeroed() };
-        
+
         cmd.cdw0 = NVME_CMD_FLUSH as u32;
         cmd.nsid = nsid;
-        
-        self.submit_admin_cmd(&cmd)?;
-        
-        Ok(())
+
+        self.submit_and_wait(&cmd)
+    }
+
+    // Deallocate (TRIM) `ranges` of (starting LBA, block count) pairs via
+    // the Dataset Management command with the Attribute-Deallocate bit set.
+    pub fn discard(&mut self, nsid: u32, ranges: &[(u64, u32)]) -> Result<(), i32> {
+        if self.oncs & NVME_ONCS_DSM == 0 {
+            return Err(-95); // ENOTSUP-ish: namespace never advertised DSM
+        }
+        if ranges.is_empty() || ranges.len() > NVME_DSM_MAX_RANGES {
+            return Err(-22);
+        }
+
+        let size = ranges.len() * mem::size_of::<NvmeDsmRange>();
+        let (virt, bus) = self.dma.alloc_coherent(size).ok_or(-12)?;
+
+        let descs = virt as *mut NvmeDsmRange;
+        for (i, &(lba, nlb)) in ranges.iter().enumerate() {
+            let range = NvmeDsmRange { context_attrs: 0, length: nlb, slba: lba };
+            unsafe {
+                ptr::write_volatile(descs.add(i), range);
+            }
+        }
+
+        let mut cmd: NvmeCommand = unsafe { mem::zeroed() };
+        cmd.cdw0 = NVME_CMD_DSM as u32;
+        cmd.nsid = nsid;
+        cmd.prp1 = bus;
+        cmd.cdw10 = (ranges.len() - 1) as u32; // NR is zero-based
+        cmd.cdw11 = NVME_DSM_ATTR_DEALLOCATE;
+
+        let result = self.submit_and_wait(&cmd);
+        self.dma.free_coherent(virt, bus, size);
+        result
+    }
+
+    // Zero `blocks` logical blocks starting at `lba` without transferring
+    // any data (opcode 0x08); the controller fills the range with zeroes
+    // internally, so no PRP is set up.
+    pub fn write_zeroes(&mut self, nsid: u32, lba: u64, blocks: u16) -> Result<(), i32> {
+        if self.oncs & NVME_ONCS_WRITE_ZEROES == 0 {
+            return Err(-95); // ENOTSUP-ish: namespace never advertised Write Zeroes
+        }
+
+        let mut cmd: NvmeCommand = unsafe { mem::zeroed() };
+        cmd.cdw0 = NVME_CMD_WRITE_ZEROES as u32;
+        cmd.nsid = nsid;
+        cmd.cdw10 = (lba & 0xffffffff) as u32;
+        cmd.cdw11 = (lba >> 32) as u32;
+        cmd.cdw12 = (blocks - 1) as u32; // 0-based
+
+        self.submit_and_wait(&cmd)
+    }
+
+    // Submit `cmd` on a round-robin I/O queue, falling back to the admin
+    // queue until at least one I/O queue has been created. Resubmits up to
+    // max_retries times when a command times out before giving up.
+    fn submit_and_wait(&mut self, cmd: &NvmeCommand) -> Result<(), i32> {
+        let mut attempt = 0;
+
+        loop {
+            let result = match self.pick_io_queue() {
+                Some(qid) => {
+                    let cid = self.submit_io_cmd(qid, cmd)?;
+                    self.wait_completion(qid, cid)
+                }
+                None => {
+                    let cid = self.submit_admin_cmd(cmd)?;
+                    self.wait_completion(0, cid)
+                }
+            };
+
+            match result {
+                Err(-62) if attempt < self.max_retries => {
+                    attempt += 1;
+                    continue;
+                }
+                other => return other,
+            }
+        }
+    }
+
+    fn max_transfer_bytes(&self) -> u64 {
+        if self.max_transfer_shift == 0 {
+            u64::MAX
+        } else {
+            (self.page_size as u64) << self.max_transfer_shift
+        }
+    }
+
+    // Map `buf` and lay out prp1/prp2 (allocating PRP list pages through the
+    // DmaProvider when the transfer spans more than two pages), chaining
+    // additional list pages when one itself overflows.
+    fn build_prp(&mut self, buf: *const u8, len: usize) -> Result<PrpResources, i32> {
+        if len == 0 {
+            return Err(-22);
+        }
+
+        let buf_bus = self.dma.map(buf, len).ok_or(-5)?;
+        let page_size = self.page_size as u64;
+        let page_mask = page_size - 1;
+        let offset_in_page = buf_bus & page_mask;
+        let bytes_in_first_page = page_size - offset_in_page;
+
+        if (len as u64) <= bytes_in_first_page {
+            return Ok(PrpResources { prp1: buf_bus, prp2: 0, list: None, buf_bus, buf_len: len });
+        }
+
+        let mut remaining = len as u64 - bytes_in_first_page;
+        let mut next_page_bus = (buf_bus & !page_mask) + page_size;
+
+        if remaining <= page_size {
+            return Ok(PrpResources { prp1: buf_bus, prp2: next_page_bus, list: None, buf_bus, buf_len: len });
+        }
+
+        let entries_per_page = (page_size / 8) as usize;
+        let mut pages: Vec<(*mut u8, u64)> = Vec::new();
+        let (first_list_virt, first_list_bus) = self.dma.alloc_coherent(self.page_size as usize).ok_or(-12)?;
+        pages.push((first_list_virt, first_list_bus));
+
+        let mut list_virt = first_list_virt;
+        let mut slot = 0usize;
+
+        while remaining > 0 {
+            if slot == entries_per_page - 1 && remaining > page_size {
+                // The last slot of a full list page chains to a fresh one.
+                let (next_virt, next_bus) = self.dma.alloc_coherent(self.page_size as usize).ok_or(-12)?;
+                unsafe {
+                    ptr::write_volatile((list_virt as *mut u64).add(slot), next_bus);
+                }
+                pages.push((next_virt, next_bus));
+                list_virt = next_virt;
+                slot = 0;
+                continue;
+            }
+
+            unsafe {
+                ptr::write_volatile((list_virt as *mut u64).add(slot), next_page_bus);
+            }
+            next_page_bus += page_size;
+            remaining = remaining.saturating_sub(page_size);
+            slot += 1;
+        }
+
+        Ok(PrpResources { prp1: buf_bus, prp2: first_list_bus, list: Some(pages), buf_bus, buf_len: len })
+    }
+
+    fn release_prp(&mut self, res: PrpResources) {
+        self.dma.unmap(res.buf_bus, res.buf_len);
+        if let Some(pages) = res.list {
+            for (virt, bus) in pages {
+                self.dma.free_coherent(virt, bus, self.page_size as usize);
+            }
+        }
+    }
+
+    // Drain outstanding completions, request the controller shut down via
+    // CC.SHN, and poll CSTS.SHST until it reports complete (or abrupt time
+    // runs out). `abrupt` selects the faster, unclean shutdown notification.
+    pub fn shutdown(&mut self, abrupt: bool) -> Result<(), i32> {
+        for qid in 0..=self.max_qid {
+            while self.process_cq(qid).is_some() {}
+        }
+
+        let shn = if abrupt { NVME_CC_SHN_ABRUPT } else { NVME_CC_SHN_NORMAL };
+        self.ctrl_config = (self.ctrl_config & !NVME_CC_SHN_MASK) | shn;
+        self.write_reg32(NVME_REG_CC, self.ctrl_config);
+
+        // CAP.TO counts 500ms units the controller may take to respond;
+        // fall back to it when the caller hasn't set shutdown_timeout.
+        let cap_to = ((self.cap >> NVME_CAP_TO_SHIFT) & NVME_CAP_TO_MASK) as u16;
+        let base = if self.shutdown_timeout != 0 { self.shutdown_timeout } else { cap_to.max(1) };
+        let mut timeout = base as u32 * NVME_SHUTDOWN_POLL_PER_UNIT;
+
+        loop {
+            if (self.read_reg32(NVME_REG_CSTS) & NVME_CSTS_SHST_MASK) == NVME_CSTS_SHST_CMPLT {
+                return Ok(());
+            }
+
+            timeout -= 1;
+            if timeout == 0 {
+                return Err(-62); // shutdown never completed
+            }
+        }
+    }
+}
+
+// Busy-loop iterations spent polling CSTS.SHST per CAP.TO 500ms unit.
+const NVME_SHUTDOWN_POLL_PER_UNIT: u32 = 10_000;
+
+// Logical block size assumed for a namespace that has no entry in the
+// geometry cache (active LBA format from identify_namespace).
+const DEFAULT_LBA_SIZE: u64 = 512;
+
+struct PrpResources {
+    prp1: u64,
+    prp2: u64,
+    list: Option<Vec<(*mut u8, u64)>>,
+    buf_bus: u64,
+    buf_len: usize,
+}
+
+// Status code type bucket width used to spread SCT/SC pairs across a
+// stable negative error range (generic, command-specific, media, vendor).
+const NVME_STATUS_BUCKET: i32 = 0x100;
+// Iterations spent polling a CQ (admin or I/O) before giving up on a completion.
+const NVME_CQ_POLL_ITERS: u32 = 1_000_000;
+// Bounded retry count for commands that time out, mirroring Linux's
+// default nvme_max_retries.
+const NVME_DEFAULT_MAX_RETRIES: u8 = 5;
+
+// Decode a completion's status field (bit 0 is the phase tag, bits 1-15
+// hold the status code type in the high byte and the status code in the
+// low byte) into a driver Result so callers can distinguish media errors,
+// invalid fields, etc. instead of only learning "it failed".
+fn decode_status(status: u16) -> Result<(), i32> {
+    let sc_sct = status >> 1;
+    if sc_sct == 0 {
+        return Ok(());
+    }
+
+    let sc = (sc_sct & 0xff) as i32;
+    let sct = ((sc_sct >> 8) & 0x7) as i32;
+
+    Err(-(NVME_STATUS_BUCKET * (sct + 1) + sc))
+}
+
+// C-supplied DMA callbacks, wrapped in `CDmaProvider` to back the
+// `DmaProvider` trait when the controller is driven from the embedding OS
+// rather than from Rust-native code.
+#[repr(C)]
+pub struct DmaOps {
+    pub alloc_coherent: extern "C" fn(size: usize, bus_addr_out: *mut u64) -> *mut u8,
+    pub free_coherent: extern "C" fn(virt: *mut u8, bus_addr: u64, size: usize),
+    pub map: extern "C" fn(virt: *const u8, size: usize) -> u64,
+    pub unmap: extern "C" fn(bus_addr: u64, size: usize),
+}
+
+struct CDmaProvider {
+    ops: DmaOps,
+}
+
+impl DmaProvider for CDmaProvider {
+    fn alloc_coherent(&mut self, size: usize) -> Option<(*mut u8, u64)> {
+        let mut bus_addr: u64 = 0;
+        let virt = (self.ops.alloc_coherent)(size, &mut bus_addr as *mut u64);
+        if virt.is_null() {
+            None
+        } else {
+            Some((virt, bus_addr))
+        }
+    }
+
+    fn free_coherent(&mut self, virt: *mut u8, bus_addr: u64, size: usize) {
+        (self.ops.free_coherent)(virt, bus_addr, size);
+    }
+
+    fn map(&mut self, virt: *const u8, size: usize) -> Option<u64> {
+        let bus_addr = (self.ops.map)(virt, size);
+        if bus_addr == 0 {
+            None
+        } else {
+            Some(bus_addr)
+        }
+    }
+
+    fn unmap(&mut self, bus_addr: u64, size: usize) {
+        (self.ops.unmap)(bus_addr, size);
     }
 }
 
 // C interface functions
 #[no_mangle]
-pub extern "C" fn nvme_create_controller(bar: *mut u8) -> *mut NvmeCtrl {
-    let ctrl = Box::new(NvmeCtrl::new(bar));
+pub extern "C" fn nvme_create_controller(bar: *mut u8, dma_ops: DmaOps) -> *mut NvmeCtrl {
+    let dma = Box::new(CDmaProvider { ops: dma_ops }) as Box<dyn DmaProvider>;
+    let ctrl = Box::new(NvmeCtrl::new(bar, dma));
     Box::into_raw(ctrl)
 }
 
@@ -544,11 +1560,26 @@ pub extern "C" fn nvme_create_controller(bar: *mut u8) -> *mut NvmeCtrl {
 pub extern "C" fn nvme_destroy_controller(ctrl: *mut NvmeCtrl) {
     if !ctrl.is_null() {
         unsafe {
+            let _ = (*ctrl).shutdown(false);
             let _ = Box::from_raw(ctrl);
         }
     }
 }
 
+#[no_mangle]
+pub extern "C" fn nvme_shutdown_controller(ctrl: *mut NvmeCtrl, abrupt: bool) -> i32 {
+    if ctrl.is_null() {
+        return -1;
+    }
+
+    unsafe {
+        match (*ctrl).shutdown(abrupt) {
+            Ok(()) => 0,
+            Err(e) => e,
+        }
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn nvme_initialize_controller(ctrl: *mut NvmeCtrl) -> i32 {
     if ctrl.is_null() {
@@ -577,6 +1608,47 @@ pub extern "C" fn nvme_admin_identify(ctrl: *mut NvmeCtrl, data: *mut NvmeIdCtrl
     }
 }
 
+#[no_mangle]
+pub extern "C" fn nvme_keep_alive(ctrl: *mut NvmeCtrl) -> i32 {
+    if ctrl.is_null() {
+        return -1;
+    }
+
+    unsafe {
+        match (*ctrl).keep_alive() {
+            Ok(()) => 0,
+            Err(e) => e,
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn nvme_set_aen_callback(ctrl: *mut NvmeCtrl, cb: Option<extern "C" fn(u8, u8)>) -> i32 {
+    if ctrl.is_null() {
+        return -1;
+    }
+
+    unsafe {
+        (*ctrl).set_aen_callback(cb);
+    }
+    0
+}
+
+// Resolve queued AENs (log-page read + callback). Call this from a
+// bottom-half/tick, never from inside an IRQ handler or other context that
+// might itself be polling wait_completion.
+#[no_mangle]
+pub extern "C" fn nvme_dispatch_pending_aens(ctrl: *mut NvmeCtrl) -> i32 {
+    if ctrl.is_null() {
+        return -1;
+    }
+
+    unsafe {
+        (*ctrl).dispatch_pending_aens();
+    }
+    0
+}
+
 #[no_mangle]
 pub extern "C" fn nvme_identify_namespace(ctrl: *mut NvmeCtrl, nsid: u32, data: *mut NvmeIdNs) -> i32 {
     if ctrl.is_null() || data.is_null() {
@@ -591,14 +1663,63 @@ pub extern "C" fn nvme_identify_namespace(ctrl: *mut NvmeCtrl, nsid: u32, data:
     }
 }
 
+#[no_mangle]
+pub extern "C" fn nvme_get_log_page(ctrl: *mut NvmeCtrl, nsid: u32, log_id: u8, buffer: *mut u8, len: usize) -> i32 {
+    if ctrl.is_null() || buffer.is_null() {
+        return -1;
+    }
+
+    unsafe {
+        match (*ctrl).get_log_page(nsid, log_id, buffer, len) {
+            Ok(()) => 0,
+            Err(e) => e,
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn nvme_smart_log(ctrl: *mut NvmeCtrl, out: *mut NvmeSmartLog) -> i32 {
+    if ctrl.is_null() || out.is_null() {
+        return -1;
+    }
+
+    unsafe {
+        match (*ctrl).smart_log() {
+            Ok(log) => {
+                *out = log;
+                0
+            }
+            Err(e) => e,
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn nvme_error_log(ctrl: *mut NvmeCtrl, out: *mut NvmeErrorLogEntry, out_len: usize) -> i32 {
+    if ctrl.is_null() || out.is_null() {
+        return -1;
+    }
+
+    unsafe {
+        match (*ctrl).error_log() {
+            Ok(log) => {
+                let n = out_len.min(NVME_ERROR_LOG_ENTRIES);
+                ptr::copy_nonoverlapping(log.as_ptr(), out, n);
+                n as i32
+            }
+            Err(e) => e,
+        }
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn nvme_read_blocks(ctrl: *mut NvmeCtrl, nsid: u32, lba: u64, blocks: u16, buffer: *mut u8) -> i32 {
     if ctrl.is_null() || buffer.is_null() {
         return -1;
     }
-    
+
     unsafe {
-        match (*ctrl).read_data(nsid, lba, blocks, buffer) {
+        match (*ctrl).read_data(nsid, lba, blocks, buffer, ptr::null_mut(), None) {
             Ok(()) => 0,
             Err(e) => e,
         }
@@ -610,15 +1731,54 @@ pub extern "C" fn nvme_write_blocks(ctrl: *mut NvmeCtrl, nsid: u32, lba: u64, bl
     if ctrl.is_null() || buffer.is_null() {
         return -1;
     }
-    
+
     unsafe {
-        match (*ctrl).write_data(nsid, lba, blocks, buffer) {
+        match (*ctrl).write_data(nsid, lba, blocks, buffer, ptr::null(), None) {
             Ok(()) => 0,
             Err(e) => e,
         }
     }
 }
 
+#[no_mangle]
+pub extern "C" fn nvme_read_blocks_pi(ctrl: *mut NvmeCtrl, nsid: u32, lba: u64, blocks: u16, buffer: *mut u8, meta: *mut u8, pi: *const PiParams) -> i32 {
+    if ctrl.is_null() || buffer.is_null() {
+        return -1;
+    }
+
+    unsafe {
+        let pi = if pi.is_null() { None } else { Some(*pi) };
+        match (*ctrl).read_data(nsid, lba, blocks, buffer, meta, pi) {
+            Ok(()) => 0,
+            Err(e) => e,
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn nvme_write_blocks_pi(ctrl: *mut NvmeCtrl, nsid: u32, lba: u64, blocks: u16, buffer: *const u8, meta: *const u8, pi: *const PiParams) -> i32 {
+    if ctrl.is_null() || buffer.is_null() {
+        return -1;
+    }
+
+    unsafe {
+        let pi = if pi.is_null() { None } else { Some(*pi) };
+        match (*ctrl).write_data(nsid, lba, blocks, buffer, meta, pi) {
+            Ok(()) => 0,
+            Err(e) => e,
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn nvme_metadata_size(ctrl: *mut NvmeCtrl, nsid: u32) -> u16 {
+    if ctrl.is_null() {
+        return 0;
+    }
+
+    unsafe { (*ctrl).metadata_size(nsid) }
+}
+
 #[no_mangle]
 pub extern "C" fn nvme_flush_namespace(ctrl: *mut NvmeCtrl, nsid: u32) -> i32 {
     if ctrl.is_null() {
@@ -633,6 +1793,44 @@ pub extern "C" fn nvme_flush_namespace(ctrl: *mut NvmeCtrl, nsid: u32) -> i32 {
     }
 }
 
+// C-ABI mirror of the (lba, nlb) pairs NvmeCtrl::discard takes natively.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct NvmeDiscardRange {
+    pub lba: u64,
+    pub nlb: u32,
+}
+
+#[no_mangle]
+pub extern "C" fn nvme_discard(ctrl: *mut NvmeCtrl, nsid: u32, ranges: *const NvmeDiscardRange, count: usize) -> i32 {
+    if ctrl.is_null() || ranges.is_null() || count == 0 {
+        return -1;
+    }
+
+    unsafe {
+        let entries = slice::from_raw_parts(ranges, count);
+        let pairs: Vec<(u64, u32)> = entries.iter().map(|r| (r.lba, r.nlb)).collect();
+        match (*ctrl).discard(nsid, &pairs) {
+            Ok(()) => 0,
+            Err(e) => e,
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn nvme_write_zeroes(ctrl: *mut NvmeCtrl, nsid: u32, lba: u64, blocks: u16) -> i32 {
+    if ctrl.is_null() {
+        return -1;
+    }
+
+    unsafe {
+        match (*ctrl).write_zeroes(nsid, lba, blocks) {
+            Ok(()) => 0,
+            Err(e) => e,
+        }
+    }
+}
+
 // Panic handler for no_std environment
 #[panic_handler]
 fn panic(_info: &core::panic::PanicInfo) -> ! {
@@ -647,3 +1845,4 @@ fn alloc_error_handler(_layout: core::alloc::Layout) -> ! {
 
 extern crate alloc;
 use alloc::boxed::Box;
+use alloc::vec::Vec;